@@ -0,0 +1,31 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use mdurl::{format_url_for_humans, format_url_for_humans_with, FormatOptions, QueryElision};
+
+fn format(c: &mut Criterion) {
+    c.bench_function("reddit url", |b| {
+        let url = "https://www.reddit.com/r/programming/comments/vxttiq/comment/ifyqsqt/?utm_source=reddit&utm_medium=web2x&context=3";
+        b.iter(|| format_url_for_humans(black_box(url), 30));
+    });
+
+    c.bench_function("long path", |b| {
+        let url = format!("https://example.com/{}file.html", "directory/".repeat(100));
+        b.iter(|| format_url_for_humans(black_box(&url), 30));
+    });
+
+    c.bench_function("deep host", |b| {
+        let url = format!("https://{}example.com/", "subdomain.".repeat(100));
+        b.iter(|| format_url_for_humans(black_box(&url), 30));
+    });
+
+    for count in [1000, 16000, 64000] {
+        c.bench_function(&format!("query parameters {count}"), |b| {
+            let query = (0..count).map(|i| format!("param{i}=value{i}")).collect::<Vec<_>>().join("&");
+            let url = format!("https://example.com/search?{query}");
+            let options = FormatOptions { max_length: 80, query_elision: QueryElision::Parameters, ..Default::default() };
+            b.iter(|| format_url_for_humans_with(black_box(&url), &options));
+        });
+    }
+}
+
+criterion_group!(benches, format);
+criterion_main!(benches);
@@ -36,6 +36,16 @@ pub fn main() {
         decoded.style().set_property("height", "0").unwrap();
         decoded.style().set_property("height", &format!("{}px", decoded.scroll_height())).unwrap();
 
+        let trace = document.get_element_by_id("trace").expect("no #trace");
+        let options = mdurl::FormatOptions { max_length: maxlen, ..Default::default() };
+        let rows = mdurl::format_url_for_humans_trace(&src, &options).into_iter().map(|stage| {
+            format!(
+                "<tr><td>{:?}</td><td class=\"trace-length\">{}</td><td><code>{}</code></td></tr>",
+                stage.kind, stage.length, escape_html(&stage.text),
+            )
+        }).collect::<String>();
+        trace.set_inner_html(&rows);
+
         let encoded = document.get_element_by_id("encoded").expect("no #encoded");
         let encoded = encoded.dyn_ref::<web_sys::HtmlTextAreaElement>().unwrap();
         encoded.set_inner_html(&mdurl::format_url_for_computers(&src));
@@ -81,3 +91,7 @@ pub fn main() {
     maxlen.add_event_listener_with_callback("input", input_handler.as_ref().unchecked_ref()).unwrap();
     input_handler.forget();
 }
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
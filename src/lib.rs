@@ -12,12 +12,43 @@
 //! assert_eq!(format(url, 30), "www.reddit.com/r/…/ifyqsqt/?u…");
 //! assert_eq!(format(url, 50), "www.reddit.com/r/programming/comments/…/ifyqsqt/?…");
 //! ```
+//!
+//! ## Features
+//!
+//! This crate is `#![no_std]` and only requires `alloc`.
+//!
+//!  - `idna` (enabled by default) - encode hostnames with punycode in
+//!    [format_url_for_computers] and decode them back in [format_url_for_humans].
+//!    Disable default features if you need to build for a target without `std`,
+//!    hostnames will be left as is in that case.
+//!
+//!  - `encoding` - decode and encode urls percent-encoded in legacy character encodings
+//!    (e.g. windows-1251 or Shift_JIS) using [encoding_rs](https://crates.io/crates/encoding_rs),
+//!    see `FormatOptions::fallback_encoding`.
+//!
+//!  - `grapheme` - never split grapheme clusters (e.g. flag emoji) when eliding urls using
+//!    [unicode-segmentation](https://crates.io/crates/unicode-segmentation),
+//!    see [FormatOptions::grapheme_boundaries].
+//!
+//!  - `psl` - find registrable domain of hostnames (e.g. `example.co.uk` or `user.github.io`)
+//!    using embedded [Public Suffix List](https://publicsuffix.org/) (licensed under MPL-2.0),
+//!    so [format_url_for_humans] never elides it, see `PublicSuffixList`,
+//!    `FormatOptions::public_suffix_list` and `Url::registrable_domain`.
+//!    The list is embedded as sorted plain text rather than a compiled trie, which keeps
+//!    lookups simple and the crate free of build scripts, but makes a stripped release
+//!    binary about 135 KB larger (123 KB of rules plus lookup code).
+//!
+//!  - `width` - measure urls in display columns instead of characters using
+//!    [unicode-width](https://crates.io/crates/unicode-width), see `WidthMode::Columns`.
+//!
+#![no_std]
+#![cfg_attr(docsrs, feature(doc_cfg))]
 #![forbid(unsafe_code)]
 #![warn(clippy::manual_assert)]
 #![warn(clippy::semicolon_if_nothing_returned)]
 
-#[doc(hidden)]
-// not part of official API, see comments in that module
+extern crate alloc;
+
 pub mod urlencode;
 
 mod parse;
@@ -29,3 +60,29 @@ pub use url::Url;
 mod format;
 pub use format::format_url_for_computers;
 pub use format::format_url_for_humans;
+pub use format::format_url_for_humans_with;
+pub use format::format_url_for_humans_measured;
+pub use format::format_url_for_humans_measured_with;
+pub use format::format_url_for_humans_segments;
+pub use format::format_url_for_humans_highlighted;
+pub use format::format_url_for_humans_trace;
+pub use format::FormatOptions;
+pub use format::QueryElision;
+
+#[cfg(feature = "psl")]
+mod psl;
+#[cfg(feature = "psl")]
+#[cfg_attr(docsrs, doc(cfg(feature = "psl")))]
+pub use psl::PublicSuffixList;
+
+mod segment;
+pub use segment::Segment;
+pub use segment::SegmentKind;
+
+mod trace;
+pub use trace::TraceStage;
+pub use trace::TraceStageKind;
+
+mod width;
+pub use width::TextMeasure;
+pub use width::WidthMode;
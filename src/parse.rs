@@ -41,84 +41,167 @@
 //    which can be constructed using other parts of the url.
 //
 
-use once_cell::sync::Lazy;
-use regex::Regex;
-use std::collections::HashSet;
+use alloc::string::String;
 use crate::Url;
 
 // Reference: RFC 3986, RFC 1808, RFC 2396
 
-// define these here so at least they only have to be
-// compiled once on the first module load.
-static PROTOCOL_PATTERN : Lazy<Regex> = Lazy::new(||
-    Regex::new(r#"(?i)^([a-z0-9.+-]+:)"#).unwrap()
-);
-
-static PORT_PATTERN : Lazy<Regex> = Lazy::new(||
-    Regex::new(r#":[0-9]*$"#).unwrap()
-);
-
-//static HOST_PATTERN : Lazy<Regex> = Lazy::new(||
-//    Regex::new(r#"^//[^@/]+@[^@/]+"#).unwrap()
-//);
+// Character classes used by the parser, stored as bit flags in a lookup table
+// indexed by byte, so each character is classified with a single load.
+//
+// Everything here is ASCII, bytes >= 0x80 never belong to any class.
+//
+const PROTOCOL_CHAR      : u8 = 1 << 0; // [a-zA-Z0-9.+-]
+const HOSTNAME_PART_CHAR : u8 = 1 << 1; // [a-zA-Z0-9+_-]
+const HOST_ENDING_CHAR   : u8 = 1 << 2;
+const NON_HOST_CHAR      : u8 = 1 << 3;
 
-// Special case for a simple path URL
-//static SIMPLE_PATH_PATTERN : Lazy<Regex> = Lazy::new(||
-//    Regex::new(r#"^(//?[^/\?\s]?[^\?\s]*)(\?[^\s]*)?$"#).unwrap()
-//);
+const HOST_ENDING_CHARS : &[u8] = b"/?#";
 
-const NON_HOST_CHARS : [ char; 8 + 6 + 1 + 5 ] = [
+const NON_HOST_CHARS : &[u8] = concat!(
     // RFC 2396: characters reserved for delimiting URLs.
     // We actually just auto-escape these.
-    '<', '>', '"', '`', ' ', '\r', '\n', '\t', // DELIMS
+    "<>\"` \r\n\t", // DELIMS
 
     // RFC 2396: characters not allowed for various reasons.
-    '{', '}', '|', '\\', '^', '`', // UNWISE
+    "{}|\\^`", // UNWISE
 
     // Allowed by RFCs, but cause of XSS attacks.  Always escape these.
-    '\'', // AUTO_ESCAPE
+    "'", // AUTO_ESCAPE
 
     // Characters that are never ever allowed in a hostname.
     // Note that any invalid chars are also handled, but these
     // are the ones that are *expected* to be seen, so we fast-path
     // them.
-    '%', '/', '?', ';', '#', // NON_HOST_CHARS
-];
+    "%/?;#", // NON_HOST_CHARS
+).as_bytes();
+
+static CHAR_CLASS : [ u8; 256 ] = build_char_class_table();
+
+const fn build_char_class_table() -> [ u8; 256 ] {
+    let mut table = [ 0; 256 ];
+    let mut byte = 0;
+
+    while byte < 0x80 {
+        let ch = byte as u8;
+        if ch.is_ascii_alphanumeric() {
+            table[byte] |= PROTOCOL_CHAR | HOSTNAME_PART_CHAR;
+        }
+        if ch == b'+' || ch == b'-' {
+            table[byte] |= PROTOCOL_CHAR | HOSTNAME_PART_CHAR;
+        }
+        if ch == b'.' {
+            table[byte] |= PROTOCOL_CHAR;
+        }
+        if ch == b'_' {
+            table[byte] |= HOSTNAME_PART_CHAR;
+        }
+        byte += 1;
+    }
+
+    let mut i = 0;
+    while i < HOST_ENDING_CHARS.len() {
+        table[HOST_ENDING_CHARS[i] as usize] |= HOST_ENDING_CHAR;
+        i += 1;
+    }
+
+    let mut i = 0;
+    while i < NON_HOST_CHARS.len() {
+        table[NON_HOST_CHARS[i] as usize] |= NON_HOST_CHAR;
+        i += 1;
+    }
+
+    table
+}
+
+#[inline]
+fn is_class(byte: u8, class: u8) -> bool {
+    CHAR_CLASS[byte as usize] & class != 0
+}
+
+// Find first byte of a given class, all classes are ASCII,
+// so returned position is always on a char boundary.
+#[inline]
+fn find_class(bytes: &[u8], class: u8) -> Option<usize> {
+    bytes.iter().position(|&b| is_class(b, class))
+}
+
+// Equivalent of `(?i)^([a-z0-9.+-]+:)` regexp, returns protocol length
+// including trailing colon.
+fn match_protocol(bytes: &[u8]) -> Option<usize> {
+    let len = bytes.iter().take_while(|&&b| is_class(b, PROTOCOL_CHAR)).count();
+
+    if len > 0 && bytes.get(len) == Some(&b':') {
+        Some(len + 1)
+    } else {
+        None
+    }
+}
 
-const HOST_ENDING_CHARS : [ char; 3 ] = [ '/', '?', '#' ];
+// Equivalent of `:[0-9]*$` regexp, returns port length including leading colon.
+fn match_port(bytes: &[u8]) -> Option<usize> {
+    let digits = bytes.iter().rev().take_while(|b| b.is_ascii_digit()).count();
 
-static HOSTNAME_PART_PATTERN : Lazy<Regex> = Lazy::new(||
-    Regex::new(r#"^[+a-z0-9A-Z_-]{0,63}$"#).unwrap()
-);
+    if bytes.len() > digits && bytes[bytes.len() - digits - 1] == b':' {
+        Some(digits + 1)
+    } else {
+        None
+    }
+}
+
+const HOSTNAME_PART_MAX_LENGTH : usize = 63;
+
+// Equivalent of checking `^[+a-z0-9A-Z_-]{0,63}$` regexp against a hostname part
+// with all non-ASCII characters replaced by a placeholder.
+//
+// Non-ASCII characters are counted once per char (continuation bytes are skipped),
+// so the size of hostname is not broken by multibyte sequences.
+fn is_hostname_part(bytes: &[u8]) -> bool {
+    let mut chars = 0;
+
+    for &byte in bytes {
+        if byte < 0x80 {
+            if !is_class(byte, HOSTNAME_PART_CHAR) { return false; }
+            chars += 1;
+        } else if byte & 0xc0 != 0x80 {
+            chars += 1;
+        }
+    }
 
-static HOSTNAME_PART_START : Lazy<Regex> = Lazy::new(||
-    Regex::new(r#"^([+a-z0-9A-Z_-]{0,63})(.*)$"#).unwrap()
-);
+    chars <= HOSTNAME_PART_MAX_LENGTH
+}
+
+// Equivalent of `^([+a-z0-9A-Z_-]{0,63})(.*)$` regexp, returns the length of the first group.
+fn match_hostname_part_start(bytes: &[u8]) -> usize {
+    bytes.iter()
+        .take(HOSTNAME_PART_MAX_LENGTH)
+        .take_while(|&&b| is_class(b, HOSTNAME_PART_CHAR))
+        .count()
+}
 
 // protocols that can allow "unsafe" and "unwise" chars.
 // protocols that never have a hostname.
-static HOSTLESS_PROTOCOL : Lazy<HashSet<&'static str>> = Lazy::new(||
-    HashSet::from_iter([
-        "javascript",
-        "javascript:",
-    ].iter().copied())
-);
+fn is_hostless_protocol(proto: &str) -> bool {
+    // protocol always ends with a colon, and this check is case-sensitive
+    proto == "javascript:"
+}
 
 // protocols that always contain a // bit.
-static SLASHED_PROTOCOL : Lazy<HashSet<&'static str>> = Lazy::new(||
-    HashSet::from_iter([
-        "http",
-        "https",
-        "ftp",
-        "gopher",
-        "file",
-        "http:",
-        "https:",
-        "ftp:",
-        "gopher:",
-        "file:",
-    ].iter().copied())
-);
+const SLASHED_PROTOCOL : [ &str; 5 ] = [
+    "http:",
+    "https:",
+    "ftp:",
+    "gopher:",
+    "file:",
+];
+
+fn is_slashed_protocol(proto: &str) -> bool {
+    SLASHED_PROTOCOL.contains(&proto)
+}
+
+fn is_slashed_protocol_ignore_case(proto: &str) -> bool {
+    SLASHED_PROTOCOL.iter().any(|p| p.eq_ignore_ascii_case(proto))
+}
 
 
 /// Parse URL string and return a [Url] object.
@@ -150,31 +233,29 @@ static SLASHED_PROTOCOL : Lazy<HashSet<&'static str>> = Lazy::new(||
 ///
 pub fn parse_url(url: &str) -> Url {
     let mut this = Url::default();
-    let mut rest = url;
 
     // trim before proceeding.
     // This is to support parse stuff like "  http://foo.com  \n"
-    rest = rest.trim();
+    let mut rest = url.trim();
 
-    if let Some(proto_match) = PROTOCOL_PATTERN.captures(rest) {
-        let proto = Some(proto_match.get(0).unwrap().as_str());
-        //let lower_proto = proto.map(|s| s.to_ascii_lowercase());
-        this.protocol = proto.map(|s| s.into());
-        rest = &rest[proto.unwrap().len()..];
+    let mut protocol = None;
+    if let Some(proto_len) = match_protocol(rest.as_bytes()) {
+        protocol = Some(&rest[..proto_len]);
+        rest = &rest[proto_len..];
     }
 
+    let hostless = protocol.map(is_hostless_protocol).unwrap_or(false);
+
     // figure out if it's got a host
     // user@server is *always* interpreted as a hostname, and url
     // resolution will treat //foo/bar as host=foo,path=bar because that's
     // how the browser resolves relative URLs.
-    let slashes = rest.starts_with("//");
-    if slashes && !(this.protocol.is_some() && HOSTLESS_PROTOCOL.contains(this.protocol.as_ref().unwrap().as_str())) {
+    if !hostless && rest.starts_with("//") {
         rest = &rest[2..];
         this.slashes = true;
     }
 
-    if (this.protocol.is_none() || !HOSTLESS_PROTOCOL.contains(this.protocol.as_ref().unwrap().as_str())) &&
-        (this.slashes || (this.protocol.is_some() && !SLASHED_PROTOCOL.contains(this.protocol.as_ref().unwrap().as_str()))) {
+    if !hostless && (this.slashes || protocol.map(|p| !is_slashed_protocol(p)).unwrap_or(false)) {
 
         // there's a hostname.
         // the first instance of /, ?, ;, or # ends the host.
@@ -192,18 +273,15 @@ pub fn parse_url(url: &str) -> Url {
         // Review our test case against browsers more comprehensively.
 
         // find the first instance of any hostEndingChars
-        let host_end = rest.find(HOST_ENDING_CHARS);
+        let bytes = rest.as_bytes();
+        let host_end = find_class(bytes, HOST_ENDING_CHAR).unwrap_or(bytes.len());
 
         // at this point, either we have an explicit point where the
         // auth portion cannot go past, or the last @ char is the decider.
-        let at_sign = if let Some(host_end) = host_end {
-            // atSign must be in auth portion.
-            // http://a@b/c@d => host:b auth:a path:/c@d
-            rest[..host_end].rfind('@')
-        } else {
-            // atSign can be anywhere.
-            rest.rfind('@')
-        };
+        //
+        // atSign must be in auth portion.
+        // http://a@b/c@d => host:b auth:a path:/c@d
+        let at_sign = bytes[..host_end].iter().rposition(|&b| b == b'@');
 
         // Now we have a portion which is definitely the auth.
         // Pull that off.
@@ -213,68 +291,59 @@ pub fn parse_url(url: &str) -> Url {
         }
 
         // the host is the remaining to the left of the first non-host char
-        let host_end = rest.find(NON_HOST_CHARS);
+        let bytes = rest.as_bytes();
         // if we still have not hit it, then the entire thing is a host.
-        let mut host_end = host_end.unwrap_or(rest.len());
+        let mut host_end = find_class(bytes, NON_HOST_CHAR).unwrap_or(bytes.len());
 
-        if rest[..host_end].ends_with(':') { host_end -= 1; }
+        if host_end > 0 && bytes[host_end - 1] == b':' { host_end -= 1; }
+        // host with everything after it, host is always a prefix of this
+        let host_and_rest = rest;
         let mut host = &rest[..host_end];
         rest = &rest[host_end..];
 
         // pull out port.
-        if let Some(port_match) = PORT_PATTERN.captures(host) {
-            let port = port_match.get(0).unwrap().as_str();
-            if port != ":" {
-                this.port = Some(port[1..].into());
+        if let Some(port_len) = match_port(host.as_bytes()) {
+            if port_len > 1 {
+                this.port = Some(host[host.len()-port_len+1..].into());
             }
-            host = &host[..host.len()-port.len()];
+            host = &host[..host.len()-port_len];
         }
-        this.hostname = Some(host.into());
 
         // if hostname begins with [ and ends with ]
         // assume that it's an IPv6 address.
-        let check_hostname = this.hostname.as_ref().unwrap().as_str();
-        let ipv6_hostname = check_hostname.starts_with('[') && check_hostname.ends_with(']');
-
-        // validate a little.
-        if !ipv6_hostname {
-            let hostparts = this.hostname.as_ref().unwrap().split('.').collect::<Vec<_>>();
-            for (i, part) in hostparts.iter().enumerate() {
-                if part.is_empty() { continue; }
-                if !HOSTNAME_PART_PATTERN.is_match(part) {
-                    // we replace non-ASCII char with a temporary placeholder
-                    // we need this to make sure size of hostname is not
-                    // broken by replacing non-ASCII by nothing
-                    let newpart = part.chars()
-                        .map(|c| if c as u32 > 127 { 'x' } else { c })
-                        .collect::<String>();
-                    // we test again with ASCII char only
-                    if !HOSTNAME_PART_PATTERN.is_match(&newpart) {
-                        let mut valid_parts = hostparts[..i].to_vec();
-                        let mut not_host = hostparts[i+1..].to_vec();
-                        if let Some(bit) = HOSTNAME_PART_START.captures(part) {
-                            valid_parts.push(bit.get(1).unwrap().as_str());
-                            not_host.push(bit.get(2).unwrap().as_str());
-                        }
-                        if !not_host.is_empty() {
-                            // same as:
-                            //rest = not_host.join(".") + rest;
-                            rest = &url[url.len()-rest.len()-not_host.join(".").len()..];
-                        }
-                        this.hostname = Some(valid_parts.join("."));
-                        //this.hostname = Some(&url[url.len()-rest.len()-valid_parts.join(".").len()..url.len()-rest.len()]);
-                        break;
-                    }
+        let ipv6_hostname = host.starts_with('[') && host.ends_with(']');
+
+        if ipv6_hostname {
+            // strip [ and ] from the hostname
+            // the host field still retains them, though
+            host = &host[1..host.len()-1];
+        } else {
+            // validate a little.
+            let bytes = host.as_bytes();
+            let mut part_start = 0;
+
+            while part_start <= bytes.len() {
+                let part_end = bytes[part_start..].iter()
+                    .position(|&b| b == b'.')
+                    .map_or(bytes.len(), |pos| part_start + pos);
+                let part = &bytes[part_start..part_end];
+
+                if !part.is_empty() && !is_hostname_part(part) {
+                    let valid_end = part_start + match_hostname_part_start(part);
+
+                    // everything after the valid part goes to the path,
+                    // including port, which is not a port of this host anymore
+                    rest = &host_and_rest[valid_end..];
+                    host = &host[..valid_end];
+                    this.port = None;
+                    break;
                 }
+
+                part_start = part_end + 1;
             }
         }
 
-        // strip [ and ] from the hostname
-        // the host field still retains them, though
-        if ipv6_hostname {
-            let hostname = this.hostname.as_ref().unwrap().as_str();
-            this.hostname = Some(hostname[1..hostname.len()-1].into());
-        }
+        this.hostname = Some(host.into());
     }
 
     // chop off from the tail first.
@@ -290,12 +359,12 @@ pub fn parse_url(url: &str) -> Url {
     if !rest.is_empty() {
         this.pathname = Some(rest.into());
     }
-    if this.protocol.is_some() &&
-            SLASHED_PROTOCOL.contains(this.protocol.as_ref().unwrap().to_ascii_lowercase().as_str()) &&
-            this.hostname.is_some() && !this.hostname.as_ref().unwrap().is_empty() &&
+    if protocol.map(is_slashed_protocol_ignore_case).unwrap_or(false) &&
+            this.hostname.as_ref().map(|h| !h.is_empty()).unwrap_or(false) &&
             this.pathname.is_none() {
         this.pathname = Some(String::new());
     }
 
+    this.protocol = protocol.map(|s| s.into());
     this
 }
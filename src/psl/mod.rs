@@ -0,0 +1,397 @@
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cmp::Ordering;
+
+/// [Public Suffix List](https://publicsuffix.org/), used to find registrable domain
+/// of a hostname (e.g. `example.co.uk` or `user.github.io`), so it's never elided.
+///
+/// A snapshot of the list is embedded into this crate, see [PublicSuffixList::embedded].
+/// You can load a newer version with [PublicSuffixList::parse]:
+///
+/// ```rust
+/// use mdurl::{format_url_for_humans_with, FormatOptions, PublicSuffixList};
+///
+/// // normally it's read from disk, e.g. `/usr/share/publicsuffix/public_suffix_list.dat`
+/// let dat = "// ===BEGIN ICANN DOMAINS===\ncom\nuk\nco.uk\n// ===END ICANN DOMAINS===\n";
+/// let list = PublicSuffixList::parse(dat);
+///
+/// assert_eq!(list.public_suffix("www.example.co.uk"), Some("co.uk"));
+/// assert_eq!(list.registrable_domain("www.example.co.uk"), Some("example.co.uk"));
+///
+/// let options = FormatOptions { max_length: 16, public_suffix_list: Some(list), ..Default::default() };
+/// assert_eq!(format_url_for_humans_with("https://foo.bar.example.co.uk/", &options), "…example.co.uk");
+/// ```
+///
+/// Rules are stored as sorted plain text, one rule per line, and looked up
+/// with binary search, so the embedded list takes about 135 KB in the binary.
+/// Cloning is cheap, rules are shared between clones.
+///
+/// Requires `psl` feature.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PublicSuffixList {
+    // Sorted rules (as bytes), one per line, starting at `start`
+    // (embedded list has license and description before that).
+    rules: Rules,
+    start: usize,
+    // Maximum number of labels in a rule (e.g. 2 for `co.uk` or `*.ck`),
+    // longer suffixes of hostnames are never checked.
+    max_labels: usize,
+}
+
+#[derive(Clone)]
+enum Rules {
+    Embedded(&'static str),
+    Parsed(Arc<str>),
+}
+
+impl Rules {
+    fn as_str(&self) -> &str {
+        match self {
+            Rules::Embedded(rules) => rules,
+            Rules::Parsed(rules) => rules,
+        }
+    }
+}
+
+impl PartialEq for Rules {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Rules {}
+
+static EMBEDDED: PublicSuffixList = {
+    let rules = include_str!("rules.txt");
+    // header ends with an empty line
+    let mut start = 0;
+    while !(rules.as_bytes()[start] == b'\n' && rules.as_bytes()[start + 1] == b'\n') {
+        start += 1;
+    }
+    PublicSuffixList { rules: Rules::Embedded(rules), start: start + 2, max_labels: max_labels(rules, start + 2) }
+};
+
+// Maximum number of labels in a rule, computed at compile time for the embedded list.
+const fn max_labels(rules: &str, start: usize) -> usize {
+    let rules = rules.as_bytes();
+    let mut result = 0;
+    let mut labels = 1;
+    let mut i = start;
+    while i < rules.len() {
+        match rules[i] {
+            b'.' => labels += 1,
+            b'\n' => {
+                if labels > result { result = labels; }
+                labels = 1;
+            }
+            _ => {}
+        }
+        i += 1;
+    }
+    result
+}
+
+impl PublicSuffixList {
+    /// Snapshot of the list embedded into this crate (version 2023-02-09),
+    /// including both ICANN and private domains (e.g. `github.io`), same as browsers do.
+    pub fn embedded() -> Self {
+        EMBEDDED.clone()
+    }
+
+    /// Parse list in the `public_suffix_list.dat` format.
+    ///
+    /// Both ICANN and private domains are used. If `idna` feature is enabled,
+    /// internationalized rules are also added in punycode, so they match hostnames
+    /// in both forms.
+    pub fn parse(dat: &str) -> Self {
+        let mut rules = Vec::new();
+
+        for line in dat.lines() {
+            // each line is only read up to the first whitespace
+            let rule = line.split_whitespace().next().unwrap_or_default();
+            if rule.is_empty() || rule.starts_with("//") { continue; }
+
+            let rule = rule.to_lowercase();
+
+            #[cfg(feature = "idna")]
+            if !rule.is_ascii() {
+                let (exception, name) = match rule.strip_prefix('!') {
+                    Some(name) => ("!", name),
+                    None => ("", rule.as_str()),
+                };
+                let labels = name.split('.').map(|label| match label.is_ascii() {
+                    true => Some(label.into()),
+                    false => idna::punycode::encode_str(label).map(|s| alloc::format!("xn--{s}")),
+                }).collect::<Option<Vec<String>>>();
+
+                if let Some(labels) = labels {
+                    rules.push(alloc::format!("{}{}", exception, labels.join(".")));
+                }
+            }
+
+            rules.push(rule);
+        }
+
+        rules.sort_unstable_by(|a, b| a.as_bytes().cmp(b.as_bytes()));
+        rules.dedup();
+
+        let mut result = String::with_capacity(rules.iter().map(|r| r.len() + 1).sum());
+        for rule in rules {
+            result.push_str(&rule);
+            result.push('\n');
+        }
+        let max_labels = max_labels(&result, 0);
+        Self { rules: Rules::Parsed(result.into()), start: 0, max_labels }
+    }
+
+    /// Public suffix of the hostname, e.g. `co.uk` for `www.example.co.uk`.
+    ///
+    /// Hostname is matched case-insensitively (internationalized hostnames are
+    /// normalized first if `idna` feature is enabled), and if no rules match,
+    /// its last label is returned (e.g. `local` for `printer.local`).
+    /// Trailing dot is not included in the result.
+    ///
+    /// Returns `None` for IP addresses, empty hostnames and hostnames ending with an empty label
+    /// (e.g. `example.com..`).
+    pub fn public_suffix<'a>(&self, hostname: &'a str) -> Option<&'a str> {
+        self.public_suffix_of(strip_trailing_dot(hostname))
+    }
+
+    /// Registrable domain of the hostname, which is public suffix plus one more label,
+    /// e.g. `example.co.uk` for `www.example.co.uk`.
+    ///
+    /// Returns `None` if hostname is a public suffix itself (e.g. `co.uk` or `github.io`),
+    /// and for IP addresses.
+    pub fn registrable_domain<'a>(&self, hostname: &'a str) -> Option<&'a str> {
+        self.registrable_domain_of(strip_trailing_dot(hostname))
+    }
+
+    /// Part of the hostname before its registrable domain,
+    /// e.g. `www` for `www.example.co.uk`.
+    ///
+    /// Returns `None` if there is no such part, or no registrable domain.
+    pub fn subdomain<'a>(&self, hostname: &'a str) -> Option<&'a str> {
+        let name = strip_trailing_dot(hostname);
+        let domain = self.registrable_domain_of(name)?;
+        name[..name.len() - domain.len()].strip_suffix('.').filter(|s| !s.is_empty())
+    }
+
+    // Same as `public_suffix`, with trailing dot already removed.
+    fn public_suffix_of<'a>(&self, name: &'a str) -> Option<&'a str> {
+        if name.is_empty() || name.ends_with('.') || is_ip_address(name) {
+            return None;
+        }
+
+        let labels = self.public_suffix_labels(name);
+        let start = name.rmatch_indices('.').nth(labels - 1).map(|(pos, _)| pos + 1).unwrap_or(0);
+        Some(&name[start..])
+    }
+
+    // Same as `registrable_domain`, with trailing dot already removed.
+    fn registrable_domain_of<'a>(&self, name: &'a str) -> Option<&'a str> {
+        let suffix = self.public_suffix_of(name)?;
+        let rest = name[..name.len() - suffix.len()].strip_suffix('.')?;
+        let start = rest.rfind('.').map(|pos| pos + 1).unwrap_or(0);
+        if start == rest.len() { return None; }
+        Some(&name[start..])
+    }
+
+    // Number of labels in public suffix of a hostname (without trailing dot).
+    fn public_suffix_labels(&self, name: &str) -> usize {
+        // normalize hostname (e.g. `Bücher.DE` -> `xn--bcher-kva.de`), unless it changes labels
+        #[cfg(feature = "idna")]
+        if !name.is_ascii() {
+            if let Ok(ascii) = idna::domain_to_ascii(name) {
+                if ascii.split('.').count() == name.split('.').count() {
+                    return self.lookup(&ascii);
+                }
+            }
+        }
+
+        self.lookup(name)
+    }
+
+    // Number of labels in public suffix of a hostname according to the rules.
+    fn lookup(&self, name: &str) -> usize {
+        // check suffixes from the longest one, so the longest matching rule wins
+        // (suffixes longer than any rule can't match, so they are skipped)
+        let mut start = name.rmatch_indices('.').nth(self.max_labels.saturating_sub(1)).map(|(pos, _)| pos + 1).unwrap_or(0);
+        let labels = |start: usize| name[start..].split('.').count();
+
+        loop {
+            let suffix = &name[start..];
+            let parent = suffix.find('.').map(|pos| start + pos + 1);
+
+            // exception rule, e.g. `!www.ck` makes `ck` a public suffix for `www.ck`
+            if self.contains("!", suffix) {
+                return labels(start) - 1;
+            }
+
+            // normal rule, e.g. `co.uk`, or wildcard rule, e.g. `*.ck`
+            if self.contains("", suffix) || parent.is_some_and(|p| self.contains("*.", &name[p..])) {
+                return labels(start);
+            }
+
+            match parent {
+                Some(parent) => start = parent,
+                // default rule `*`, last label is a public suffix
+                None => return 1,
+            }
+        }
+    }
+
+    // Check if `prefix + name` is in the list, name is compared case-insensitively.
+    fn contains(&self, prefix: &str, name: &str) -> bool {
+        let rules = self.rules.as_str().as_bytes();
+        let mut lo = self.start;
+        let mut hi = rules.len();
+
+        // binary search over lines, `lo` and `hi` are always at the start of a line
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let start = rules[lo..mid].iter().rposition(|&b| b == b'\n').map(|pos| lo + pos + 1).unwrap_or(lo);
+            let end = rules[start..].iter().position(|&b| b == b'\n').map(|pos| start + pos).unwrap_or(rules.len());
+
+            match compare(&rules[start..end], prefix, name) {
+                Ordering::Equal => return true,
+                Ordering::Less => lo = end + 1,
+                Ordering::Greater => hi = start,
+            }
+        }
+
+        false
+    }
+}
+
+// Hostname without a single trailing dot, e.g. `example.com.` -> `example.com`.
+fn strip_trailing_dot(hostname: &str) -> &str {
+    hostname.strip_suffix('.').unwrap_or(hostname)
+}
+
+// Compare rule with `prefix + name`, with name lowercased.
+fn compare(rule: &[u8], prefix: &str, name: &str) -> Ordering {
+    let key = prefix.bytes().chain(name.bytes().map(|b| b.to_ascii_lowercase()));
+    rule.iter().copied().cmp(key)
+}
+
+// Check if hostname is an IP address: IPv6 (contains `:`) or IPv4,
+// which is any hostname ending with a number (same as in WHATWG URL standard).
+fn is_ip_address(name: &str) -> bool {
+    if name.contains(':') {
+        return true;
+    }
+
+    let last = name.rsplit('.').next().unwrap_or_default();
+    let (digits, radix) = match last.strip_prefix("0x").or_else(|| last.strip_prefix("0X")) {
+        Some(hex) => (hex, 16),
+        None => (last, 10),
+    };
+    !digits.is_empty() && digits.chars().all(|ch| ch.is_digit(radix))
+}
+
+impl core::fmt::Debug for PublicSuffixList {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        let embedded = matches!(self.rules, Rules::Embedded(_));
+        f.debug_struct("PublicSuffixList").field("embedded", &embedded).finish_non_exhaustive()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::PublicSuffixList;
+
+    #[test]
+    fn public_suffix() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.public_suffix("www.example.com"), Some("com"));
+        assert_eq!(list.public_suffix("www.example.co.uk"), Some("co.uk"));
+        assert_eq!(list.public_suffix("t.co"), Some("co"));
+        assert_eq!(list.public_suffix("example.com.au"), Some("com.au"));
+        assert_eq!(list.public_suffix("user.github.io"), Some("github.io"));
+        assert_eq!(list.public_suffix("printer.local"), Some("local"));
+        assert_eq!(list.public_suffix("WWW.Example.CO.UK"), Some("CO.UK"));
+        assert_eq!(list.public_suffix("example.com."), Some("com"));
+        assert_eq!(list.public_suffix("com"), Some("com"));
+        assert_eq!(list.public_suffix(""), None);
+    }
+
+    #[test]
+    fn wildcards_and_exceptions() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.public_suffix("foo.bar.ck"), Some("bar.ck"));
+        assert_eq!(list.public_suffix("foo.www.ck"), Some("ck"));
+        assert_eq!(list.registrable_domain("foo.www.ck"), Some("www.ck"));
+    }
+
+    #[test]
+    fn registrable_domain() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.registrable_domain("www.example.co.uk"), Some("example.co.uk"));
+        assert_eq!(list.registrable_domain("foo.blogspot.com"), Some("foo.blogspot.com"));
+        assert_eq!(list.registrable_domain("example.com."), Some("example.com"));
+        assert_eq!(list.registrable_domain("github.io"), None);
+        assert_eq!(list.registrable_domain("com"), None);
+        assert_eq!(list.registrable_domain(".com"), None);
+        assert_eq!(list.registrable_domain(""), None);
+    }
+
+    #[test]
+    fn subdomain() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.subdomain("foo.bar.example.co.uk"), Some("foo.bar"));
+        assert_eq!(list.subdomain("www.example.com."), Some("www"));
+        assert_eq!(list.subdomain("example.com"), None);
+        assert_eq!(list.subdomain("co.uk"), None);
+    }
+
+    #[test]
+    fn empty_last_label() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.public_suffix("example.com.."), None);
+        assert_eq!(list.public_suffix("example.日本..."), None);
+        assert_eq!(list.registrable_domain("日本.."), None);
+        assert_eq!(list.registrable_domain("example.日本語.."), None);
+        assert_eq!(list.registrable_domain("example.日本語..."), None);
+        assert_eq!(list.subdomain("日本語.com.."), None);
+        assert_eq!(list.subdomain("www.日本語.com..."), None);
+    }
+
+    #[test]
+    fn ip_addresses() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.public_suffix("127.0.0.1"), None);
+        assert_eq!(list.public_suffix("0x7f.1"), None);
+        assert_eq!(list.public_suffix("::1"), None);
+        assert_eq!(list.registrable_domain("192.168.0.1"), None);
+        assert_eq!(list.registrable_domain("1.example.com"), Some("example.com"));
+        assert_eq!(list.public_suffix("foo.0x"), Some("0x"));
+        assert_eq!(list.registrable_domain("example.0X"), Some("example.0X"));
+    }
+
+    #[test]
+    fn internationalized_rules() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.registrable_domain("www.пример.рф"), Some("пример.рф"));
+        assert_eq!(list.registrable_domain("www.xn--e1afmkfd.xn--p1ai"), Some("xn--e1afmkfd.xn--p1ai"));
+    }
+
+    #[test]
+    #[cfg(feature = "idna")]
+    fn internationalized_hostnames_are_normalized() {
+        let list = PublicSuffixList::embedded();
+        assert_eq!(list.public_suffix("example.РФ"), Some("РФ"));
+        assert_eq!(list.registrable_domain("www.Пример.РФ"), Some("Пример.РФ"));
+    }
+
+    #[test]
+    fn parse() {
+        let dat = "// comment\n\n*.Foo\n!bar.foo  comment\nexample\n";
+        let list = PublicSuffixList::parse(dat);
+        assert_eq!(list.public_suffix("a.b.foo"), Some("b.foo"));
+        assert_eq!(list.public_suffix("a.bar.foo"), Some("foo"));
+        assert_eq!(list.public_suffix("a.example"), Some("example"));
+        assert_eq!(list.public_suffix("a.com"), Some("com"));
+    }
+}
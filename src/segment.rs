@@ -0,0 +1,219 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+
+/// Part of a formatted url, returned by [format_url_for_humans_segments](crate::format_url_for_humans_segments).
+///
+/// ```rust
+/// use mdurl::{format_url_for_humans_segments, FormatOptions, SegmentKind};
+///
+/// let url = "https://www.example.org/a/b/c/d/file.html?q=1";
+/// let options = FormatOptions { max_length: 32, ..Default::default() };
+/// let segments = format_url_for_humans_segments(url, &options);
+///
+/// let html = segments.iter().map(|s| match s.kind {
+///     SegmentKind::RegistrableDomain => format!("<b>{}</b>", s.text),
+///     SegmentKind::Ellipsis => format!("<i>{}</i>", s.text),
+///     _ => s.text.clone(),
+/// }).collect::<String>();
+///
+/// assert_eq!(html, "www.<b>example.org</b>/a/<i>…</i>/file.html?q<i>…</i>");
+/// assert_eq!(&url[segments[2].source.clone()], "/a/");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Segment {
+    /// What part of the url this segment represents.
+    pub kind: SegmentKind,
+
+    /// Text of this segment, concatenated texts of all segments are
+    /// the same as the result of [format_url_for_humans_with](crate::format_url_for_humans_with).
+    pub text: String,
+
+    /// Byte range of the source url this segment came from.
+    ///
+    /// For [SegmentKind::Ellipsis] it is the range of elided text.
+    /// Separators that belong to a segment are included (e.g. `@` after auth
+    /// or `:` before port).
+    pub source: Range<usize>,
+}
+
+/// Kind of [Segment].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum SegmentKind {
+    /// Protocol with slashes, e.g. `ftp://`.
+    Scheme,
+    /// Username and password with trailing `@`, e.g. `user:pass@`.
+    Auth,
+    /// Part of hostname before registrable domain, e.g. `www.`,
+    /// or the entire hostname if it's an IP address.
+    Host,
+    /// Registrable domain, e.g. `example.org` or `example.co.uk`.
+    RegistrableDomain,
+    /// Port with leading `:`, e.g. `:8080`.
+    Port,
+    /// Pathname, e.g. `/foo/bar`.
+    Path,
+    /// Query string with leading `?`, e.g. `?q=1`.
+    Query,
+    /// Fragment with leading `#`, e.g. `#top`.
+    Fragment,
+    /// Replacement for elided part of the url, e.g. `…`.
+    Ellipsis,
+}
+
+// Mapping from offsets in a formatted text to offsets in the source url,
+// stored as a list of `(text offset, source offset)` points sorted by both.
+// Text between the points is assumed to be copied from the source as is.
+//
+// First and last points are stored separately, so the most common case
+// (text copied or replaced as a whole) doesn't allocate.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SourceMap {
+    first: (usize, usize),
+    inner: Vec<(usize, usize)>,
+    last: (usize, usize),
+}
+
+impl SourceMap {
+    // Text that is copied from `source` range as is.
+    pub(crate) fn identity(source: Range<usize>) -> Self {
+        Self::replacement(source.len(), source)
+    }
+
+    // Text that replaces `source` range entirely, e.g. an ellipsis.
+    pub(crate) fn replacement(text_len: usize, source: Range<usize>) -> Self {
+        Self { first: (0, source.start), inner: Vec::new(), last: (text_len, source.end) }
+    }
+
+    // Map for a text built from points in order, starting at `(0, source start)`.
+    pub(crate) fn from_points(first: (usize, usize), inner: Vec<(usize, usize)>, last: (usize, usize)) -> Self {
+        Self { first, inner, last }
+    }
+
+    // Source offset corresponding to the text offset.
+    pub(crate) fn map(&self, offset: usize) -> usize {
+        if offset >= self.last.0 {
+            return self.last.1 + (offset - self.last.0);
+        }
+
+        // points right before and after the offset
+        let i = self.inner.partition_point(|&(text, _)| text <= offset);
+        let (text, source) = if i == 0 { self.first } else { self.inner[i - 1] };
+        let (_, next) = self.inner.get(i).copied().unwrap_or(self.last);
+        (source + offset.saturating_sub(text)).min(next)
+    }
+
+    // Text range corresponding to the source range, rounded outwards
+    // if it starts or ends inside of a replaced text.
+    pub(crate) fn unmap(&self, source: Range<usize>) -> Range<usize> {
+        let points = core::iter::once(self.first)
+            .chain(self.inner.iter().copied())
+            .chain(core::iter::once(self.last))
+            .collect::<Vec<_>>();
+        let is_copied = |(text, source): (usize, usize), (next_text, next_source): (usize, usize)| {
+            next_text - text == next_source - source
+        };
+
+        // last point before the start
+        let i = points.partition_point(|&(_, s)| s <= source.start).saturating_sub(1);
+        let start = match points.get(i + 1) {
+            Some(&next) if is_copied(points[i], next) => points[i].0 + source.start.saturating_sub(points[i].1),
+            _ => points[i].0,
+        };
+
+        // first point after the end
+        let j = points.partition_point(|&(_, s)| s < source.end).min(points.len() - 1);
+        let end = match j.checked_sub(1).map(|i| points[i]) {
+            Some(prev) if is_copied(prev, points[j]) => prev.0 + (source.end - prev.1).min(points[j].1 - prev.1),
+            _ => points[j].0,
+        };
+
+        start.min(self.last.0)..end.max(start)
+    }
+
+    pub(crate) fn source(&self) -> Range<usize> {
+        self.first.1..self.last.1
+    }
+
+    // Mapping for `text[range]`.
+    pub(crate) fn slice(&self, range: Range<usize>) -> Self {
+        let inner = self.inner.iter()
+            .filter(|&&(text, _)| text > range.start && text < range.end)
+            .map(|&(text, source)| (text - range.start, source))
+            .collect();
+        Self::from_points((0, self.map(range.start)), inner, (range.len(), self.map(range.end)))
+    }
+
+    // Mapping for `prefix + text + suffix`, where prefix and suffix
+    // are located right before and after the text in the source.
+    pub(crate) fn wrap(&self, prefix: &str, text_len: usize, suffix: &str, source: &str) -> Self {
+        let Range { start, end } = self.source();
+        // prefix and suffix are empty most of the time, so they aren't compared then
+        let start = match !prefix.is_empty() && source[..start].ends_with(prefix) {
+            true => start - prefix.len(),
+            false => start,
+        };
+        let end = match !suffix.is_empty() && source[end..].starts_with(suffix) {
+            true => end + suffix.len(),
+            false => end,
+        };
+
+        let mut inner = Vec::new();
+        if !prefix.is_empty() {
+            inner.push((prefix.len(), self.first.1));
+        }
+        for &(text, source) in &self.inner {
+            inner.push((text + prefix.len(), source));
+        }
+        if !suffix.is_empty() {
+            inner.push((prefix.len() + text_len, self.last.1));
+        }
+        Self::from_points((0, start), inner, (prefix.len() + text_len + suffix.len(), end))
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::SourceMap;
+
+    #[test]
+    fn map_should_interpolate_between_points() {
+        // "a%20b" -> "a b"
+        let map = SourceMap::from_points((0, 10), alloc::vec![(1, 11), (2, 14)], (3, 15));
+        assert_eq!([0, 1, 2, 3].map(|i| map.map(i)), [10, 11, 14, 15]);
+        assert_eq!(map.slice(1..3).source(), 11..15);
+        assert_eq!(map.slice(2..3).source(), 14..15);
+    }
+
+    #[test]
+    fn map_should_clamp_to_next_point() {
+        // "日本" (6 bytes) -> "xn--wgv71a" (10 bytes)
+        let map = SourceMap::replacement(6, 0..10);
+        assert_eq!(map.map(3), 3);
+        let map = SourceMap::replacement(10, 0..6);
+        assert_eq!(map.map(8), 6);
+    }
+
+    #[test]
+    fn unmap_should_round_outwards() {
+        // "a%20b" -> "a b"
+        let map = SourceMap::from_points((0, 10), alloc::vec![(1, 11), (2, 14)], (3, 15));
+        assert_eq!(map.unmap(10..15), 0..3);
+        assert_eq!(map.unmap(12..13), 1..2);
+        assert_eq!(map.unmap(14..15), 2..3);
+        assert_eq!(map.unmap(0..100), 0..3);
+        let map = SourceMap::replacement(6, 0..10);
+        assert_eq!(map.unmap(3..4), 0..6);
+    }
+
+    #[test]
+    fn wrap_should_include_separators() {
+        let source = "http://user@host:80/";
+        let map = SourceMap::identity(17..19).wrap(":", 2, "", source);
+        assert_eq!(map.source(), 16..19);
+        let map = SourceMap::identity(7..11).wrap("", 4, "@", source);
+        assert_eq!(map.source(), 7..12);
+    }
+}
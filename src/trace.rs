@@ -0,0 +1,57 @@
+use alloc::string::String;
+
+/// Intermediate result of url formatting, returned by [format_url_for_humans_trace](crate::format_url_for_humans_trace).
+///
+/// ```rust
+/// use mdurl::{format_url_for_humans_trace, FormatOptions, TraceStageKind};
+///
+/// let url = "https://www.example.org/a/b/c/d/file.html";
+/// let options = FormatOptions { max_length: 25, ..Default::default() };
+/// let trace = format_url_for_humans_trace(url, &options);
+///
+/// let kinds = trace.iter().map(|stage| stage.kind).collect::<Vec<_>>();
+/// assert_eq!(kinds, [
+///     TraceStageKind::Decoded,
+///     TraceStageKind::ProtocolStripped,
+///     TraceStageKind::PathComponentsRemoved,
+///     TraceStageKind::WwwRemoved,
+/// ]);
+/// assert_eq!(trace[3].text, "example.org/…/file.html");
+/// assert_eq!(trace[3].length, 23);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TraceStage {
+    /// What was done to the url at this stage.
+    pub kind: TraceStageKind,
+
+    /// Url after this stage, the text of the last stage is the same as
+    /// the result of [format_url_for_humans_with](crate::format_url_for_humans_with).
+    pub text: String,
+
+    /// Length of the text, as measured for [FormatOptions::max_length](crate::FormatOptions::max_length).
+    pub length: usize,
+}
+
+/// Kind of [TraceStage].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum TraceStageKind {
+    /// Url with percent-encoded and punycode parts decoded, nothing is removed yet.
+    Decoded,
+    /// Protocol and trailing slash are removed, see [FormatOptions::strip_protocol](crate::FormatOptions::strip_protocol).
+    ProtocolStripped,
+    /// Opaque ids in path are abbreviated, see [FormatOptions::abbreviate_ids](crate::FormatOptions::abbreviate_ids).
+    IdsAbbreviated,
+    /// Path components between the first one and the filename are removed.
+    PathComponentsRemoved,
+    /// Leading `www.` is removed, see [FormatOptions::strip_www](crate::FormatOptions::strip_www).
+    WwwRemoved,
+    /// Subdomains are removed up to registrable domain.
+    SubdomainsRemoved,
+    /// Middle of the filename is removed, see [FormatOptions::elide_filename](crate::FormatOptions::elide_filename).
+    FilenameElided,
+    /// Query parameters are shortened, see [QueryElision::Parameters](crate::QueryElision::Parameters).
+    QueryElided,
+    /// The end of the url is truncated to fit into [FormatOptions::max_length](crate::FormatOptions::max_length).
+    Truncated,
+}
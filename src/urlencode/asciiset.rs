@@ -1,7 +1,21 @@
+use core::fmt;
+
 /// Represents a set of characters or bytes in the ASCII range.
 ///
 /// Similar to <https://github.com/servo/rust-url/blob/master/percent_encoding/src/lib.rs>
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+///
+/// All methods are `const`, so sets can be composed at compile time:
+///
+/// ```rust
+/// use mdurl::urlencode::AsciiSet;
+///
+/// const HEX : AsciiSet = AsciiSet::new().add_range(b'0', b'9').add_range(b'a', b'f');
+/// const LETTERS : AsciiSet = AsciiSet::new().add_range(b'a', b'z');
+///
+/// assert_eq!(HEX.intersection(LETTERS), AsciiSet::from("abcdef"));
+/// assert_eq!(format!("{:?}", HEX.intersection(LETTERS)), "{'a', 'b', 'c', 'd', 'e', 'f'}");
+/// ```
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
 pub struct AsciiSet(u128);
 
 impl AsciiSet {
@@ -33,10 +47,39 @@ impl AsciiSet {
         Self(self.0 & !(1 << byte))
     }
 
-    pub(super) const fn add_alphanumeric(&self) -> Self {
+    /// Add all characters in `from..=to` range to the set.
+    ///
+    /// `from` and `to` must be in `0x00..0x7f` range, function will panic otherwise
+    pub const fn add_range(&self, from: u8, to: u8) -> Self {
+        assert!(from <= 0x7f && to <= 0x7f, "range must be in 0x00..0x7f");
+        if from > to {
+            return Self(self.0);
+        }
+        let count = (to - from) as u32 + 1;
+        let mask = if count == 128 { u128::MAX } else { ((1 << count) - 1) << from };
+        Self(self.0 | mask)
+    }
+
+    /// Add all ASCII letters and digits (`A-Za-z0-9`) to the set.
+    pub const fn add_alphanumeric(&self) -> Self {
         Self(self.0 | 0x07fffffe07fffffe03ff000000000000)
     }
 
+    /// Return a set with characters that are in either of the sets.
+    pub const fn union(&self, other: Self) -> Self {
+        Self(self.0 | other.0)
+    }
+
+    /// Return a set with characters that are in both sets.
+    pub const fn intersection(&self, other: Self) -> Self {
+        Self(self.0 & other.0)
+    }
+
+    /// Return a set with all ASCII characters that are not in this set.
+    pub const fn complement(&self) -> Self {
+        Self(!self.0)
+    }
+
     /// Check if a character is in the set.
     ///
     /// `byte` must be in `0x00..0x7f` range, function will panic otherwise
@@ -45,6 +88,19 @@ impl AsciiSet {
         self.0 & 1 << byte != 0
     }
 
+    /// Iterate over all characters in the set in ascending order.
+    ///
+    /// ```rust
+    /// use mdurl::urlencode::AsciiSet;
+    ///
+    /// let chars = AsciiSet::from("?#/").iter().collect::<Vec<_>>();
+    /// assert_eq!(chars, b"#/?");
+    /// ```
+    pub fn iter(&self) -> impl Iterator<Item = u8> {
+        let set = *self;
+        (0..=0x7f).filter(move |&byte| set.has(byte))
+    }
+
     const fn add_many(&self, bytes: &[u8], idx: usize) -> Self {
         if idx == bytes.len() {
             Self(self.0)
@@ -54,8 +110,22 @@ impl AsciiSet {
     }
 }
 
+impl fmt::Debug for AsciiSet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter().map(|byte| byte as char)).finish()
+    }
+}
+
+impl Default for AsciiSet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
+    use alloc::format;
+    use alloc::vec::Vec;
     use super::AsciiSet;
 
     #[test]
@@ -127,4 +197,37 @@ mod tests {
     fn add_remove() {
         assert_eq!(AsciiSet::new().add(0x20).remove(0x20), AsciiSet::new());
     }
+
+    #[test]
+    #[should_panic]
+    fn add_range_higher_byte() {
+        AsciiSet::new().add_range(0x70, 0x90);
+    }
+
+    #[test]
+    fn add_range() {
+        assert_eq!(AsciiSet::new().add_range(b'a', b'e'), AsciiSet::from("abcde"));
+        assert_eq!(AsciiSet::new().add_range(b'a', b'a'), AsciiSet::from("a"));
+        assert_eq!(AsciiSet::new().add_range(b'e', b'a'), AsciiSet::new());
+        assert_eq!(AsciiSet::new().add_range(0, 0x7f), AsciiSet::new().complement());
+        assert_eq!(AsciiSet::new().add_range(0x7e, 0x7f).iter().collect::<Vec<_>>(), [0x7e, 0x7f]);
+    }
+
+    #[test]
+    fn set_algebra() {
+        let a = AsciiSet::from("abc");
+        let b = AsciiSet::from("bcd");
+        assert_eq!(a.union(b), AsciiSet::from("abcd"));
+        assert_eq!(a.intersection(b), AsciiSet::from("bc"));
+        assert!(!a.complement().has(b'a'));
+        assert!(a.complement().has(b'd'));
+        assert!(a.complement().has(0x7f));
+        assert_eq!(a.complement().complement(), a);
+    }
+
+    #[test]
+    fn debug_should_list_chars() {
+        assert_eq!(format!("{:?}", AsciiSet::from("b\n a")), r"{'\n', ' ', 'a', 'b'}");
+        assert_eq!(format!("{:?}", AsciiSet::new()), "{}");
+    }
 }
@@ -0,0 +1,103 @@
+use alloc::borrow::Cow;
+use super::{encode, whatwg, AsciiSet};
+
+// Each set is a corresponding WHATWG set with `%` removed, so a stray `%`
+// is encoded, while existing escape sequences are kept (`keep_escaped`).
+const PATH_SEGMENT : AsciiSet = whatwg::PATH.remove(b'%').remove(b'/').remove(b'\\');
+const QUERY_VALUE  : AsciiSet = whatwg::QUERY.remove(b'%').remove(b'&').remove(b'=').remove(b'+');
+const FRAGMENT     : AsciiSet = whatwg::FRAGMENT.remove(b'%');
+const USERINFO     : AsciiSet = whatwg::USERINFO.remove(b'%');
+
+/// Encode a single path segment, e.g. `a/b c` -> `a%2Fb%20c`.
+///
+/// Path separators (`/` and `\`) are encoded along with characters from
+/// [whatwg::PATH] set, already escaped sequences are kept as is.
+///
+/// ```rust
+/// use mdurl::urlencode::encode_path_segment;
+///
+/// assert_eq!(encode_path_segment("50% off/sale?"), "50%25%20off%2Fsale%3F");
+/// assert_eq!(encode_path_segment("caf%C3%A9"), "caf%C3%A9");
+/// ```
+pub fn encode_path_segment(string: &str) -> Cow<'_, str> {
+    encode(string, PATH_SEGMENT, true)
+}
+
+/// Encode a name or a value in a query string, e.g. `a&b=c` -> `a%26b%3Dc`.
+///
+/// Pair separators (`&`, `=`) and `+` are encoded along with characters from
+/// [whatwg::QUERY] set, already escaped sequences are kept as is.
+/// Unlike [encode_form](super::encode_form), spaces are encoded as `%20`.
+///
+/// ```rust
+/// use mdurl::urlencode::encode_query_value;
+///
+/// assert_eq!(encode_query_value("rust & c++"), "rust%20%26%20c%2B%2B");
+/// ```
+pub fn encode_query_value(string: &str) -> Cow<'_, str> {
+    encode(string, QUERY_VALUE, true)
+}
+
+/// Encode a fragment (without leading `#`), e.g. `a b` -> `a%20b`.
+///
+/// Characters from [whatwg::FRAGMENT] set are encoded, already escaped sequences are kept as is.
+///
+/// ```rust
+/// use mdurl::urlencode::encode_fragment;
+///
+/// assert_eq!(encode_fragment("section <2>"), "section%20%3C2%3E");
+/// ```
+pub fn encode_fragment(string: &str) -> Cow<'_, str> {
+    encode(string, FRAGMENT, true)
+}
+
+/// Encode a username or a password, e.g. `user@host` -> `user%40host`.
+///
+/// Characters from [whatwg::USERINFO] set are encoded, already escaped sequences are kept as is.
+///
+/// ```rust
+/// use mdurl::urlencode::encode_userinfo;
+///
+/// assert_eq!(encode_userinfo("me@example.org:p/ss"), "me%40example.org%3Ap%2Fss");
+/// ```
+pub fn encode_userinfo(string: &str) -> Cow<'_, str> {
+    encode(string, USERINFO, true)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use super::{encode_fragment, encode_path_segment, encode_query_value, encode_userinfo};
+
+    #[test]
+    fn should_keep_escaped_sequences() {
+        for encode in [encode_path_segment, encode_query_value, encode_fragment, encode_userinfo] {
+            assert_eq!(encode("%20%zz%"), "%20%25zz%25");
+            assert!(matches!(encode("abc%2F"), Cow::Borrowed("abc%2F")));
+        }
+    }
+
+    #[test]
+    fn path_segment() {
+        assert_eq!(encode_path_segment("a/b\\c?d#e"), "a%2Fb%5Cc%3Fd%23e");
+        assert_eq!(encode_path_segment("a:b@c;d=e&f"), "a:b@c;d=e&f");
+    }
+
+    #[test]
+    fn query_value() {
+        assert_eq!(encode_query_value("a&b=c+d#e f"), "a%26b%3Dc%2Bd%23e%20f");
+        assert_eq!(encode_query_value("/?:@"), "/?:@");
+    }
+
+    #[test]
+    fn fragment() {
+        assert_eq!(encode_fragment("a b`c#d?e/f"), "a%20b%60c#d?e/f");
+    }
+
+    #[test]
+    fn userinfo() {
+        assert_eq!(encode_userinfo("a:b@c/d"), "a%3Ab%40c%2Fd");
+        assert_eq!(encode_userinfo("日本"), "%E6%97%A5%E6%9C%AC");
+    }
+}
@@ -1,11 +1,10 @@
-use once_cell::sync::Lazy;
-use regex::Regex;
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::convert::Infallible;
+use core::fmt;
 use super::AsciiSet;
-
-static URLENCODED_SEQUENCE : Lazy<Regex> = Lazy::new(||
-    Regex::new(r#"(%[a-fA-F0-9]{2})+"#).unwrap()
-);
+use super::utf8::{Utf8Step, Utf8Validator};
 
 /// Equivalent to [decodeURI](https://developer.mozilla.org/en-US/docs/Web/JavaScript/Reference/Global_Objects/decodeURI)
 /// character set.
@@ -23,6 +22,9 @@ pub const DECODE_COMPONENT_CHARS : AsciiSet = AsciiSet::from("");
 ///  - string        - string to decode
 ///  - exclude       - list of characters to ignore
 ///
+/// Invalid UTF-8 sequences are replaced with `U+FFFD` (same as in `String::from_utf8_lossy`).
+/// If nothing is decoded, original string is returned as `Cow::Borrowed`.
+///
 /// ```rust
 /// use mdurl::urlencode::AsciiSet;
 /// use mdurl::urlencode::decode;
@@ -30,29 +32,390 @@ pub const DECODE_COMPONENT_CHARS : AsciiSet = AsciiSet::from("");
 /// const SAFE_SET : AsciiSet = AsciiSet::from(";/?:@&=+$,#");
 /// assert_eq!(decode("%5Bhello%5D", SAFE_SET), "[hello]");
 /// ```
-pub fn decode(string: &str, exclude: AsciiSet) -> Cow<str> {
-    URLENCODED_SEQUENCE.replace_all(string, |caps: &regex::Captures| -> String {
-        let mut result = Vec::new();
-        let mut bytes = caps.get(0).unwrap().as_str().as_bytes().iter();
-
-        while bytes.next().is_some() { // skips '%'
-            let byte1 = *bytes.next().unwrap();
-            let byte2 = *bytes.next().unwrap();
-            let decoded = (parse_hex_digit(byte1) << 4) + parse_hex_digit(byte2);
-
-            if decoded < 0x80 && exclude.has(decoded) {
-                result.push(b'%');
-                result.push(byte1);
-                result.push(byte2);
-            } else {
-                result.push(decoded);
-            }
+pub fn decode(string: &str, exclude: AsciiSet) -> Cow<'_, str> {
+    let result = decode_utf8(string, exclude, |error| -> Result<_, Infallible> {
+        Ok(lossy_replacement(error))
+    });
+    result.unwrap_or_else(|never| match never {})
+}
+
+/// Decode percent-encoded characters, keeping invalid UTF-8 sequences as is.
+///
+/// Same as [decode], but only escape sequences that form valid UTF-8 characters
+/// are decoded, and the rest is left as original `%XX` text, so no information is lost.
+/// Encoding the result with `keep_escaped` (and the same exclude set) gives back original string.
+///
+/// ```rust
+/// use mdurl::urlencode::{decode_lossless, DECODE_DEFAULT_CHARS};
+///
+/// assert_eq!(decode_lossless("%E2%9C%94%80", DECODE_DEFAULT_CHARS), "✔%80");
+/// ```
+pub fn decode_lossless(string: &str, exclude: AsciiSet) -> Cow<'_, str> {
+    let result = decode_utf8(string, exclude, |_| -> Result<_, Infallible> { Ok(None) });
+    result.unwrap_or_else(|never| match never {})
+}
+
+/// Percent-decoded string, which is decoded lazily when formatted with [Display](fmt::Display).
+///
+/// It gives the same result as [decode] or [decode_lossless], but doesn't allocate,
+/// so it can be written directly into an output buffer.
+///
+/// ```rust
+/// use mdurl::urlencode::{Decoded, DECODE_DEFAULT_CHARS};
+///
+/// let html = format!("<a>{}</a>", Decoded::new("%5Bhello%5D%2F", DECODE_DEFAULT_CHARS));
+/// assert_eq!(html, "<a>[hello]%2F</a>");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Decoded<'a> {
+    string: &'a str,
+    exclude: AsciiSet,
+    lossless: bool,
+}
+
+impl<'a> Decoded<'a> {
+    /// Decode `string` same as [decode] does.
+    pub fn new(string: &'a str, exclude: AsciiSet) -> Self {
+        Self { string, exclude, lossless: false }
+    }
+
+    /// Decode `string` same as [decode_lossless] does.
+    pub fn lossless(string: &'a str, exclude: AsciiSet) -> Self {
+        Self { string, exclude, lossless: true }
+    }
+}
+
+impl fmt::Display for Decoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        decode_utf8_to(self.string, self.exclude, self.lossless, f)
+    }
+}
+
+/// Decode percent-encoded characters, returning an error on any invalid input.
+///
+/// Same as [decode], but instead of gracefully handling invalid input it rejects:
+///
+///  - `%` not followed by two hex digits, e.g. `%2g` or `100%`
+///  - escape sequences that don't form valid UTF-8, including overlong forms
+///    (e.g. `%c1%bf`) and surrogates (e.g. `%ed%a0%80`)
+///
+/// Escaped characters from `exclude` list are still kept as is.
+///
+/// ```rust
+/// use mdurl::urlencode::{decode_strict, DecodeError, DecodeErrorKind, DECODE_DEFAULT_CHARS};
+///
+/// assert_eq!(decode_strict("%5Bhello%5D%2F", DECODE_DEFAULT_CHARS).unwrap(), "[hello]%2F");
+/// assert_eq!(
+///     decode_strict("hello%2gworld", DECODE_DEFAULT_CHARS),
+///     Err(DecodeError { offset: 5, kind: DecodeErrorKind::InvalidEscape }),
+/// );
+/// assert_eq!(
+///     decode_strict("abc%c1%bf", DECODE_DEFAULT_CHARS),
+///     Err(DecodeError { offset: 3, kind: DecodeErrorKind::Overlong }),
+/// );
+/// ```
+pub fn decode_strict(string: &str, exclude: AsciiSet) -> Result<Cow<'_, str>, DecodeError> {
+    decode_utf8(string, exclude, Err)
+}
+
+/// Error returned by [decode_strict].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeError {
+    /// Byte offset of invalid sequence in the source string.
+    ///
+    /// For invalid UTF-8 it points to the start of the first escape sequence
+    /// that is a part of invalid character.
+    pub offset: usize,
+
+    /// Reason why decoding failed.
+    pub kind: DecodeErrorKind,
+}
+
+/// Kind of [DecodeError].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum DecodeErrorKind {
+    /// `%` is not followed by two hex digits, e.g. `%2g`.
+    InvalidEscape,
+    /// Decoded bytes are not valid UTF-8, e.g. `%80` or `%e3%55`.
+    InvalidUtf8,
+    /// Character is encoded with more bytes than necessary, e.g. `%c1%bf`.
+    Overlong,
+    /// Decoded bytes encode a UTF-16 surrogate, e.g. `%ed%a0%80`.
+    Surrogate,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self.kind {
+            DecodeErrorKind::InvalidEscape => "invalid escape sequence",
+            DecodeErrorKind::InvalidUtf8 => "invalid utf-8 sequence",
+            DecodeErrorKind::Overlong => "overlong utf-8 sequence",
+            DecodeErrorKind::Surrogate => "utf-16 surrogate in utf-8 sequence",
+        };
+        write!(f, "{} at byte {}", reason, self.offset)
+    }
+}
+
+impl core::error::Error for DecodeError {}
+
+/// Same as [decode], but accepts arbitrary bytes and returns raw decoded bytes
+/// without interpreting them as UTF-8.
+///
+/// If nothing is decoded, original slice is returned as `Cow::Borrowed`.
+///
+/// ```rust
+/// use mdurl::urlencode::{decode_bytes, DECODE_COMPONENT_CHARS};
+///
+/// assert_eq!(decode_bytes(b"sig=%DE%AD%BE%EF", DECODE_COMPONENT_CHARS), &b"sig=\xde\xad\xbe\xef"[..]);
+/// ```
+pub fn decode_bytes(bytes: &[u8], exclude: AsciiSet) -> Cow<'_, [u8]> {
+    let mut result : Option<Vec<u8>> = None;
+    // start of the input that wasn't copied to the result yet
+    let mut last = 0;
+
+    for token in Tokens::new(bytes, exclude) {
+        if let Token::Escape { pos, byte, excluded: false } = token {
+            // decoded string is never longer than the source, so allocate just once
+            let result = result.get_or_insert_with(|| Vec::with_capacity(bytes.len()));
+            result.extend_from_slice(&bytes[last..pos]);
+            result.push(byte);
+            last = pos + 3;
+        }
+    }
+
+    match result {
+        Some(mut result) => {
+            result.extend_from_slice(&bytes[last..]);
+            Cow::Owned(result)
         }
+        None => Cow::Borrowed(bytes),
+    }
+}
+
+// Decode all escape sequences, calling `on_invalid` for each invalid sequence,
+// which returns a character to replace it with (or `None` to keep it as is),
+// or an error to stop decoding.
+fn decode_utf8<E>(
+    string: &str,
+    exclude: AsciiSet,
+    on_invalid: impl FnMut(DecodeError) -> Result<Option<char>, E>,
+) -> Result<Cow<'_, str>, E> {
+    let mut result : Option<String> = None;
+    // start of the input that wasn't copied to the result yet
+    let mut last = 0;
 
-        String::from_utf8_lossy(&result).into()
+    for_each_replacement(string, exclude, on_invalid, |start, end, ch| {
+        // decoded string is never longer than the source, so allocate just once
+        let result = result.get_or_insert_with(|| String::with_capacity(string.len()));
+        result.push_str(&string[last..start]);
+        result.push(ch);
+        last = end;
+        Ok(())
+    })?;
+
+    Ok(match result {
+        Some(mut result) => {
+            result.push_str(&string[last..]);
+            Cow::Owned(result)
+        }
+        None => Cow::Borrowed(string),
     })
 }
 
+// Same as [decode_lossless], but also returns positions of decoded characters,
+// as `(result offset, source offset)` pairs for start and end of each one.
+pub(crate) fn decode_lossless_with_offsets(string: &str, exclude: AsciiSet) -> (Cow<'_, str>, Vec<(usize, usize)>) {
+    let mut result : Option<String> = None;
+    let mut offsets = Vec::new();
+    // start of the input that wasn't copied to the result yet
+    let mut last = 0;
+
+    let on_invalid = |_| -> Result<_, Infallible> { Ok(None) };
+    let done = for_each_replacement(string, exclude, on_invalid, |start, end, ch| {
+        let result = result.get_or_insert_with(|| String::with_capacity(string.len()));
+        result.push_str(&string[last..start]);
+        offsets.push((result.len(), start));
+        result.push(ch);
+        offsets.push((result.len(), end));
+        last = end;
+        Ok(())
+    });
+    done.unwrap_or_else(|never| match never {});
+
+    match result {
+        Some(mut result) => {
+            result.push_str(&string[last..]);
+            (Cow::Owned(result), offsets)
+        }
+        None => (Cow::Borrowed(string), offsets),
+    }
+}
+
+// Same as [decode_utf8], but writes the result into `output`.
+fn decode_utf8_to(
+    string: &str,
+    exclude: AsciiSet,
+    lossless: bool,
+    output: &mut impl fmt::Write,
+) -> fmt::Result {
+    // start of the input that wasn't written to the output yet
+    let mut last = 0;
+
+    let on_invalid = |error| Ok(if lossless { None } else { lossy_replacement(error) });
+    for_each_replacement(string, exclude, on_invalid, |start, end, ch| {
+        if last < start {
+            output.write_str(&string[last..start])?;
+        }
+        output.write_char(ch)?;
+        last = end;
+        Ok(())
+    })?;
+
+    output.write_str(&string[last..])
+}
+
+// Replacement used by lossy decoding: invalid escapes are kept as is,
+// and invalid UTF-8 sequences are replaced with `U+FFFD`.
+fn lossy_replacement(error: DecodeError) -> Option<char> {
+    if error.kind == DecodeErrorKind::InvalidEscape {
+        None
+    } else {
+        Some(char::REPLACEMENT_CHARACTER)
+    }
+}
+
+// Find all parts of the source that need to be decoded, and call `replace(start, end, ch)`
+// for each of them in order, meaning that `string[start..end]` turns into `ch`.
+fn for_each_replacement<E>(
+    string: &str,
+    exclude: AsciiSet,
+    mut on_invalid: impl FnMut(DecodeError) -> Result<Option<char>, E>,
+    mut replace: impl FnMut(usize, usize, char) -> Result<(), E>,
+) -> Result<(), E> {
+    let bytes = string.as_bytes();
+    let mut utf8 = Utf8Validator::new();
+    // start of the current utf-8 sequence in the input
+    let mut seq_start = 0;
+
+    let mut replace = |start: usize, end: usize, ch: Option<char>| {
+        match ch {
+            Some(ch) => replace(start, end, ch),
+            None => Ok(()),
+        }
+    };
+
+    for token in Tokens::new(bytes, exclude) {
+        let (pos, byte) = match token {
+            Token::Escape { pos, byte, excluded: false } => (pos, byte),
+            _ => {
+                // anything that is not decoded terminates current utf-8 sequence
+                if utf8.pending() > 0 {
+                    utf8.reset();
+                    let error = DecodeError { offset: seq_start, kind: DecodeErrorKind::InvalidUtf8 };
+                    replace(seq_start, token.start(), on_invalid(error)?)?;
+                }
+                if let Token::InvalidEscape { pos } = token {
+                    let error = DecodeError { offset: pos, kind: DecodeErrorKind::InvalidEscape };
+                    replace(pos, pos + 1, on_invalid(error)?)?;
+                }
+                continue;
+            }
+        };
+
+        if utf8.pending() == 0 {
+            seq_start = pos;
+        }
+
+        loop {
+            match utf8.push(byte) {
+                Utf8Step::Incomplete => break,
+                Utf8Step::Char(ch) => {
+                    replace(seq_start, pos + 3, Some(ch))?;
+                    break;
+                }
+                Utf8Step::Invalid { consumed: true, kind } => {
+                    let error = DecodeError { offset: pos, kind };
+                    replace(pos, pos + 3, on_invalid(error)?)?;
+                    break;
+                }
+                Utf8Step::Invalid { consumed: false, kind } => {
+                    let error = DecodeError { offset: seq_start, kind };
+                    replace(seq_start, pos, on_invalid(error)?)?;
+                    seq_start = pos;
+                }
+            }
+        }
+    }
+
+    if utf8.pending() > 0 {
+        let error = DecodeError { offset: seq_start, kind: DecodeErrorKind::InvalidUtf8 };
+        replace(seq_start, bytes.len(), on_invalid(error)?)?;
+    }
+
+    Ok(())
+}
+
+// Percent-encoded input split into parts, all functions here decode bytes
+// produced by this tokenizer, and differ only in what they do with the result.
+enum Token {
+    // bytes that don't contain `%`
+    Text { start: usize },
+    // valid escape sequence (`%[a-fA-F0-9]{2}`) decoded into a byte,
+    // `excluded` is true if this byte must be kept escaped
+    Escape { pos: usize, byte: u8, excluded: bool },
+    // `%` that doesn't start a valid escape sequence
+    InvalidEscape { pos: usize },
+}
+
+impl Token {
+    fn start(&self) -> usize {
+        match *self {
+            Token::Text { start } => start,
+            Token::Escape { pos, .. } => pos,
+            Token::InvalidEscape { pos } => pos,
+        }
+    }
+}
+
+struct Tokens<'a> {
+    bytes: &'a [u8],
+    exclude: AsciiSet,
+    pos: usize,
+}
+
+impl<'a> Tokens<'a> {
+    fn new(bytes: &'a [u8], exclude: AsciiSet) -> Self {
+        Self { bytes, exclude, pos: 0 }
+    }
+}
+
+impl Iterator for Tokens<'_> {
+    type Item = Token;
+
+    fn next(&mut self) -> Option<Token> {
+        let bytes = self.bytes;
+        let pos = self.pos;
+        if pos >= bytes.len() { return None; }
+
+        if bytes[pos] != b'%' {
+            let len = bytes[pos..].iter().position(|&b| b == b'%').unwrap_or(bytes.len() - pos);
+            self.pos += len;
+            return Some(Token::Text { start: pos });
+        }
+
+        if pos + 2 < bytes.len() && bytes[pos + 1].is_ascii_hexdigit() && bytes[pos + 2].is_ascii_hexdigit() {
+            let byte = (parse_hex_digit(bytes[pos + 1]) << 4) + parse_hex_digit(bytes[pos + 2]);
+            let excluded = byte < 0x80 && self.exclude.has(byte);
+            self.pos += 3;
+            Some(Token::Escape { pos, byte, excluded })
+        } else {
+            self.pos += 1;
+            Some(Token::InvalidEscape { pos })
+        }
+    }
+}
+
 fn parse_hex_digit(byte: u8) -> u8 {
     match byte {
         b'0'..=b'9' => byte - b'0',
@@ -65,7 +428,9 @@ fn parse_hex_digit(byte: u8) -> u8 {
 
 #[cfg(test)]
 mod tests {
-    use super::decode;
+    use alloc::borrow::Cow;
+    use alloc::string::ToString;
+    use super::{decode, decode_bytes, decode_lossless, decode_lossless_with_offsets, decode_strict, DecodeError, DecodeErrorKind, Decoded};
     use super::AsciiSet;
     const SET : AsciiSet = AsciiSet::from(";/?:@&=+$,#");
 
@@ -86,6 +451,13 @@ mod tests {
         assert_eq!(decode("%20%25%20", AsciiSet::from(" %")), "%20%25%20");
     }
 
+    #[test]
+    fn should_borrow_if_nothing_is_decoded() {
+        assert!(matches!(decode("hello world", SET), Cow::Borrowed("hello world")));
+        assert!(matches!(decode("%2f%3F%2g%", SET), Cow::Borrowed("%2f%3F%2g%")));
+        assert!(matches!(decode("%20", SET), Cow::Owned(_)));
+    }
+
     #[test]
     fn should_deal_with_utf8() {
         assert_eq!(decode("%80", SET), "\u{fffd}");
@@ -125,4 +497,121 @@ mod tests {
         assert_eq!(decode("%ed%bf%bf", SET), "\u{fffd}\u{fffd}\u{fffd}");
         assert_eq!(decode("%ee%80%80", SET), "\u{e000}");
     }
+
+    #[test]
+    fn lossless_should_keep_invalid_utf8() {
+        assert_eq!(decode_lossless("%80", SET), "%80");
+        assert_eq!(decode_lossless("%c7%55", SET), "%c7U");
+        assert_eq!(decode_lossless("%e3%95%55", SET), "%e3%95U");
+        assert_eq!(decode_lossless("%c1%bf", SET), "%c1%bf");
+        assert_eq!(decode_lossless("%ed%a0%80", SET), "%ed%a0%80");
+        assert_eq!(decode_lossless("%e3%c7%aa", SET), "%e3\u{1ea}");
+        assert_eq!(decode_lossless("%c7%aa%e3%aa%aa%f1", SET), "\u{1ea}\u{3aaa}%f1");
+        assert_eq!(decode_lossless("%c7%2f%aa", SET), "%c7%2f%aa");
+        assert_eq!(decode_lossless("%c7%20%aa", SET), "%c7 %aa");
+    }
+
+    #[test]
+    fn lossless_should_borrow_if_nothing_is_decoded() {
+        assert!(matches!(decode_lossless("%80%2f%c7", SET), Cow::Borrowed("%80%2f%c7")));
+    }
+
+    fn strict_error(string: &str) -> Option<(usize, DecodeErrorKind)> {
+        decode_strict(string, SET).err().map(|e| (e.offset, e.kind))
+    }
+
+    #[test]
+    fn strict_should_decode_xx() {
+        assert_eq!(decode_strict("x%20xx%20%2520", SET).unwrap(), "x xx %20");
+        assert_eq!(decode_strict("%20%2F%2f", SET).unwrap(), " %2F%2f");
+        assert!(matches!(decode_strict("hello", SET), Ok(Cow::Borrowed("hello"))));
+    }
+
+    #[test]
+    fn strict_should_reject_invalid_sequences() {
+        use DecodeErrorKind::*;
+        assert_eq!(strict_error("%2g%z1%%"), Some((0, InvalidEscape)));
+        assert_eq!(strict_error("abc%"), Some((3, InvalidEscape)));
+        assert_eq!(strict_error("a%2"), Some((1, InvalidEscape)));
+        assert_eq!(strict_error("%20%z1"), Some((3, InvalidEscape)));
+    }
+
+    #[test]
+    fn strict_should_deal_with_utf8() {
+        use DecodeErrorKind::*;
+        assert_eq!(strict_error("%80"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%bf"), Some((0, InvalidUtf8)));
+        assert_eq!(decode_strict("%00", SET).unwrap(), "\u{0}");
+        assert_eq!(decode_strict("%55", SET).unwrap(), "\u{55}");
+        assert_eq!(decode_strict("%7f", SET).unwrap(), "\u{7f}");
+        assert_eq!(strict_error("%c7%55"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%e3%55"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%f1%55"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%c7%c0"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%e3%c0"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%f1%c0"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%e3%95%55"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%f1%95%55"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%f1%95%95%55"), Some((0, InvalidUtf8)));
+        assert_eq!(decode_strict("%c7%aa", SET).unwrap(), "\u{1ea}");
+        assert_eq!(decode_strict("%e3%aa%aa", SET).unwrap(), "\u{3aaa}");
+        assert_eq!(decode_strict("%f1%aa%aa%aa", SET).unwrap(), "\u{6aaaa}");
+        assert_eq!(decode_strict("%c2%80", SET).unwrap(), "\u{80}");
+        assert_eq!(decode_strict("%e0%a0%80", SET).unwrap(), "\u{800}");
+        assert_eq!(strict_error("%c1%bf"), Some((0, Overlong)));
+        assert_eq!(strict_error("%e0%9f%bf"), Some((0, Overlong)));
+        assert_eq!(strict_error("%c1%80"), Some((0, Overlong)));
+        assert_eq!(strict_error("%e0%90%80"), Some((0, Overlong)));
+        assert_eq!(decode_strict("%df%bf", SET).unwrap(), "\u{7ff}");
+        assert_eq!(decode_strict("%ef%bf%bf", SET).unwrap(), "\u{ffff}");
+        assert_eq!(decode_strict("%f0%90%80%80", SET).unwrap(), "\u{10000}");
+        assert_eq!(decode_strict("%f0%90%8f%8f", SET).unwrap(), "\u{103cf}");
+        assert_eq!(decode_strict("%f4%8f%b0%80", SET).unwrap(), "\u{10fc00}");
+        assert_eq!(decode_strict("%f4%8f%bf%bf", SET).unwrap(), "\u{10ffff}");
+        assert_eq!(strict_error("%f0%8f%bf%bf"), Some((0, Overlong)));
+        assert_eq!(strict_error("%f4%90%80%80"), Some((0, InvalidUtf8)));
+        assert_eq!(strict_error("%f4%9f%bf%bf"), Some((0, InvalidUtf8)));
+        assert_eq!(decode_strict("%ed%9f%bf", SET).unwrap(), "\u{d7ff}");
+        assert_eq!(strict_error("%ed%a0%80"), Some((0, Surrogate)));
+        assert_eq!(strict_error("%ed%bf%bf"), Some((0, Surrogate)));
+        assert_eq!(decode_strict("%ee%80%80", SET).unwrap(), "\u{e000}");
+    }
+
+    #[test]
+    fn strict_should_report_offset_of_sequence_start() {
+        use DecodeErrorKind::*;
+        assert_eq!(strict_error("x%c3%a9%80"), Some((7, InvalidUtf8)));
+        assert_eq!(strict_error("ab%e3%aa"), Some((2, InvalidUtf8)));
+        assert_eq!(strict_error("ab%e3%aa/"), Some((2, InvalidUtf8)));
+        assert_eq!(strict_error("ab%e3%aa%2f%aa"), Some((2, InvalidUtf8)));
+    }
+
+    #[test]
+    fn strict_error_display() {
+        let error = DecodeError { offset: 3, kind: DecodeErrorKind::Surrogate };
+        assert_eq!(error.to_string(), "utf-16 surrogate in utf-8 sequence at byte 3");
+    }
+
+    #[test]
+    fn decode_bytes_should_keep_invalid_utf8() {
+        assert_eq!(decode_bytes(b"%80%c7%55", SET), &b"\x80\xc7\x55"[..]);
+        assert_eq!(decode_bytes(b"\xff%20%2f%2g", SET), &b"\xff %2f%2g"[..]);
+        assert!(matches!(decode_bytes(b"%2f%%", SET), Cow::Borrowed(b"%2f%%")));
+    }
+
+    #[test]
+    fn decoded_should_match_decode() {
+        for string in ["", "hello", "x%20xx%20%2520", "%E2%9C%94%80%", "%F0%9F%A6%84%f0%9f%a6", "φ%3bου%ff"] {
+            assert_eq!(Decoded::new(string, SET).to_string(), decode(string, SET));
+            assert_eq!(Decoded::lossless(string, SET).to_string(), decode_lossless(string, SET));
+        }
+    }
+
+    #[test]
+    fn lossless_with_offsets() {
+        let (decoded, offsets) = decode_lossless_with_offsets("a%20%E6%97%A5%80b%2F", SET);
+        assert_eq!(decoded, "a 日%80b%2F");
+        assert_eq!(offsets, [(1, 1), (2, 4), (2, 4), (5, 13)]);
+        assert_eq!(decoded, decode_lossless("a%20%E6%97%A5%80b%2F", SET));
+    }
 }
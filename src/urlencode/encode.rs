@@ -1,4 +1,6 @@
-use std::borrow::Cow;
+use alloc::borrow::Cow;
+use alloc::string::String;
+use core::fmt;
 use super::AsciiSet;
 
 const DIGITS : &[ u8; 16 ] = b"0123456789ABCDEF";
@@ -20,6 +22,8 @@ pub const ENCODE_COMPONENT_CHARS : AsciiSet = AsciiSet::from("-_.!~*'()");
 ///  - exclude       - list of characters to ignore (in addition to a-zA-Z0-9)
 ///  - keep_escaped  - don't encode '%' in a correct escape sequence
 ///
+/// If nothing needs to be encoded, original string is returned as `Cow::Borrowed`.
+///
 /// ```rust
 /// use mdurl::urlencode::AsciiSet;
 /// use mdurl::urlencode::encode;
@@ -27,49 +31,162 @@ pub const ENCODE_COMPONENT_CHARS : AsciiSet = AsciiSet::from("-_.!~*'()");
 /// const SAFE_SET : AsciiSet = AsciiSet::from(";/?:@&=+$,-_.!~*'()#");
 /// assert_eq!(encode("[hello]", SAFE_SET, true), "%5Bhello%5D");
 /// ```
-pub fn encode(string: &str, exclude: AsciiSet, keep_escaped: bool) -> Cow<str> {
+pub fn encode(string: &str, exclude: AsciiSet, keep_escaped: bool) -> Cow<'_, str> {
+    encode_bytes(string.as_bytes(), exclude, keep_escaped)
+}
+
+/// Same as [encode], but accepts arbitrary bytes, which don't have to be valid UTF-8.
+///
+/// All bytes outside of ASCII range are percent-encoded, so result is always a valid string.
+///
+/// ```rust
+/// use mdurl::urlencode::{encode_bytes, ENCODE_COMPONENT_CHARS};
+///
+/// assert_eq!(encode_bytes(b"\xde\xad\xbe\xef", ENCODE_COMPONENT_CHARS, true), "%DE%AD%BE%EF");
+/// ```
+pub fn encode_bytes(bytes: &[u8], exclude: AsciiSet, keep_escaped: bool) -> Cow<'_, str> {
     let exclude = exclude.add_alphanumeric();
-    let mut result = Vec::new();
-    let bytes = string.as_bytes();
-    let len = bytes.len();
     let mut i = 0;
 
-    while i < len {
-        let byte = bytes[i];
-        let should_encode = byte >= 0x80 || !exclude.has(byte);
-
-        #[allow(clippy::collapsible_if)]
-        if keep_escaped && byte == b'%' && i + 2 < len {
-            if bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit() {
-                result.push(bytes[i]);
-                result.push(bytes[i + 1]);
-                result.push(bytes[i + 2]);
-                i += 3;
-                continue;
+    while i < bytes.len() {
+        match next_chunk(bytes, i, exclude, keep_escaped) {
+            Chunk::Keep(len) => i += len,
+            Chunk::Encode(_) => {
+                let mut result = String::with_capacity(bytes.len() + 2);
+                result.push_str(ascii_str(&bytes[..i]));
+                // writing to a String never fails
+                encode_bytes_to(&bytes[i..], exclude, keep_escaped, &mut result).unwrap();
+                return Cow::Owned(result);
             }
         }
+    }
+
+    Cow::Borrowed(ascii_str(bytes))
+}
+
+/// Same as [encode], but appends the result to an existing `String`.
+///
+/// ```rust
+/// use mdurl::urlencode::{encode_into, ENCODE_DEFAULT_CHARS};
+///
+/// let mut result = String::from("https://example.org");
+/// encode_into("/my url", ENCODE_DEFAULT_CHARS, true, &mut result);
+/// assert_eq!(result, "https://example.org/my%20url");
+/// ```
+pub fn encode_into(string: &str, exclude: AsciiSet, keep_escaped: bool, output: &mut String) {
+    // writing to a String never fails
+    encode_to(string, exclude, keep_escaped, output).unwrap();
+}
+
+/// Same as [encode], but writes the result into any [fmt::Write] implementation
+/// (e.g. `String` or `fmt::Formatter`).
+///
+/// ```rust
+/// use mdurl::urlencode::{encode_to, ENCODE_COMPONENT_CHARS};
+/// use std::fmt::Write;
+///
+/// let mut result = String::from("?q=");
+/// encode_to("hello world", ENCODE_COMPONENT_CHARS, true, &mut result).unwrap();
+/// assert_eq!(result, "?q=hello%20world");
+/// ```
+pub fn encode_to(string: &str, exclude: AsciiSet, keep_escaped: bool, output: &mut impl fmt::Write) -> fmt::Result {
+    encode_bytes_to(string.as_bytes(), exclude, keep_escaped, output)
+}
+
+/// Percent-encoded string, which is encoded lazily when formatted with [Display](fmt::Display).
+///
+/// It gives the same result as [encode], but doesn't allocate,
+/// so it can be written directly into an output buffer.
+///
+/// ```rust
+/// use mdurl::urlencode::{Encoded, ENCODE_DEFAULT_CHARS};
+///
+/// let html = format!("<a href=\"{}\">", Encoded::new("/my url", ENCODE_DEFAULT_CHARS, true));
+/// assert_eq!(html, "<a href=\"/my%20url\">");
+/// ```
+#[derive(Debug, Clone, Copy)]
+pub struct Encoded<'a> {
+    string: &'a str,
+    exclude: AsciiSet,
+    keep_escaped: bool,
+}
 
-        if should_encode {
-            result.push(b'%');
-            result.push(DIGITS[(byte >> 4) as usize]);
-            result.push(DIGITS[(byte & 0xF) as usize]);
-        } else {
-            result.push(byte);
+impl<'a> Encoded<'a> {
+    /// Encode `string` same as [encode] does.
+    pub fn new(string: &'a str, exclude: AsciiSet, keep_escaped: bool) -> Self {
+        Self { string, exclude, keep_escaped }
+    }
+}
+
+impl fmt::Display for Encoded<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        encode_to(self.string, self.exclude, self.keep_escaped, f)
+    }
+}
+
+fn encode_bytes_to(bytes: &[u8], exclude: AsciiSet, keep_escaped: bool, output: &mut impl fmt::Write) -> fmt::Result {
+    let exclude = exclude.add_alphanumeric();
+    // start of the input that wasn't written to the output yet
+    let mut last = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match next_chunk(bytes, i, exclude, keep_escaped) {
+            Chunk::Keep(len) => i += len,
+            Chunk::Encode(byte) => {
+                if last < i {
+                    output.write_str(ascii_str(&bytes[last..i]))?;
+                }
+                output.write_char('%')?;
+                output.write_char(DIGITS[(byte >> 4) as usize] as char)?;
+                output.write_char(DIGITS[(byte & 0xF) as usize] as char)?;
+                i += 1;
+                last = i;
+            }
         }
+    }
+
+    output.write_str(ascii_str(&bytes[last..]))
+}
+
+// Convert bytes that are kept as is to a string, those are always ascii
+// because everything >= 0x80 is percent-encoded.
+//
+// performance note: we could use from_utf8_unchecked, but it doesn't improve speed by much
+fn ascii_str(bytes: &[u8]) -> &str {
+    core::str::from_utf8(bytes).unwrap()
+}
+
+enum Chunk {
+    // output next N bytes as is
+    Keep(usize),
+    // percent-encode this byte
+    Encode(u8),
+}
+
+fn next_chunk(bytes: &[u8], i: usize, exclude: AsciiSet, keep_escaped: bool) -> Chunk {
+    let byte = bytes[i];
 
-        i += 1;
+    #[allow(clippy::collapsible_if)]
+    if keep_escaped && byte == b'%' && i + 2 < bytes.len() {
+        if bytes[i + 1].is_ascii_hexdigit() && bytes[i + 2].is_ascii_hexdigit() {
+            return Chunk::Keep(3);
+        }
     }
 
-    // performance note:
-    // all characters are in ASCII range because everything >= 0x80 is percent-encoded,
-    // so we can use from_utf8_unchecked, but it doesn't improve speed by much
-    Cow::Owned(String::from_utf8(result).unwrap())
+    if byte >= 0x80 || !exclude.has(byte) {
+        Chunk::Encode(byte)
+    } else {
+        Chunk::Keep(1)
+    }
 }
 
 
 #[cfg(test)]
 mod tests {
-    use super::encode;
+    use alloc::borrow::Cow;
+    use alloc::string::{String, ToString};
+    use super::{encode, encode_bytes, encode_into, Encoded};
     use super::AsciiSet;
     const SET : AsciiSet = AsciiSet::from(";/?:@&=+$,-_.!~*'()#");
 
@@ -133,4 +250,33 @@ mod tests {
     fn arguments_keepescaped_false() {
         assert_eq!(encode("%20%2G", SET, false), "%2520%252G");
     }
+
+    #[test]
+    fn should_borrow_if_nothing_is_encoded() {
+        assert!(matches!(encode("hello", SET, true), Cow::Borrowed("hello")));
+        assert!(matches!(encode("%20?#", SET, true), Cow::Borrowed("%20?#")));
+        assert!(matches!(encode("%20", SET, false), Cow::Owned(_)));
+    }
+
+    #[test]
+    fn encode_into_should_append() {
+        let mut result = String::from("φ");
+        encode_into("φ[]", SET, true, &mut result);
+        assert_eq!(result, "φ%CF%86%5B%5D");
+    }
+
+    #[test]
+    fn encode_bytes_should_accept_invalid_utf8() {
+        assert_eq!(encode_bytes(b"a\xffb\x80%20 ", SET, true), "a%FFb%80%20%20");
+        assert_eq!(encode_bytes(b"\xc3\xa9", SET, true), encode("é", SET, true));
+        assert!(matches!(encode_bytes(b"abc", SET, true), Cow::Borrowed("abc")));
+    }
+
+    #[test]
+    fn encoded_should_match_encode() {
+        for string in ["", "hello", "%20%2G []", "φου%", "\x00\x7F\u{80}"] {
+            assert_eq!(Encoded::new(string, SET, true).to_string(), encode(string, SET, true));
+            assert_eq!(Encoded::new(string, SET, false).to_string(), encode(string, SET, false));
+        }
+    }
 }
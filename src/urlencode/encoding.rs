@@ -0,0 +1,190 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use encoding_rs::{EncoderResult, Encoding};
+use super::{decode_bytes, encode_bytes, AsciiSet};
+
+/// Decode percent-encoded characters from a legacy character encoding,
+/// e.g. `%EF%F0%E8%E2%E5%F2` -> `привет` in windows-1251.
+///
+///  - string        - string to decode
+///  - encoding      - character encoding of escaped bytes
+///  - exclude       - list of characters to ignore
+///
+/// Only percent-encoded bytes (and ASCII characters next to them) are decoded
+/// with `encoding`, non-ASCII characters in the source are already unicode
+/// and kept as is. Bytes that are invalid in the given encoding are replaced with `U+FFFD`.
+///
+/// `encoding` is expected to be ASCII-compatible (which is true for all encodings
+/// used in urls in practice), see [Encoding::is_ascii_compatible].
+///
+/// Requires `encoding` feature.
+///
+/// ```rust
+/// use mdurl::urlencode::{decode_with_encoding, DECODE_DEFAULT_CHARS};
+///
+/// let decoded = decode_with_encoding("?q=%EF%F0%E8%E2%E5%F2", encoding_rs::WINDOWS_1251, DECODE_DEFAULT_CHARS);
+/// assert_eq!(decoded, "?q=привет");
+///
+/// let decoded = decode_with_encoding("%93%FA%96%7B%8C%EA", encoding_rs::SHIFT_JIS, DECODE_DEFAULT_CHARS);
+/// assert_eq!(decoded, "日本語");
+/// ```
+pub fn decode_with_encoding<'a>(string: &'a str, encoding: &'static Encoding, exclude: AsciiSet) -> Cow<'a, str> {
+    let mut result : Option<String> = None;
+    // start of the input that wasn't copied to the result yet
+    let mut last = 0;
+    let mut start = 0;
+
+    // Split string into ASCII-only segments, escaped bytes of a multibyte
+    // character may be mixed with ASCII (e.g. `%83A` is `ア` in Shift_JIS),
+    // but never with non-ASCII characters, which are already decoded.
+    while start < string.len() {
+        let end = string[start..].find(|ch: char| !ch.is_ascii()).map_or(string.len(), |pos| start + pos);
+
+        if let Cow::Owned(bytes) = decode_bytes(&string.as_bytes()[start..end], exclude) {
+            let (decoded, _) = encoding.decode_without_bom_handling(&bytes);
+            let result = result.get_or_insert_with(|| String::with_capacity(string.len()));
+            result.push_str(&string[last..start]);
+            result.push_str(&decoded);
+            last = end;
+        }
+
+        // skip non-ASCII characters
+        start = string[end..].find(|ch: char| ch.is_ascii()).map_or(string.len(), |pos| end + pos);
+    }
+
+    match result {
+        Some(mut result) => {
+            result.push_str(&string[last..]);
+            Cow::Owned(result)
+        }
+        None => Cow::Borrowed(string),
+    }
+}
+
+/// Encode unsafe characters using a legacy character encoding,
+/// e.g. `привет` -> `%EF%F0%E8%E2%E5%F2` in windows-1251.
+///
+///  - string        - string to encode
+///  - encoding      - character encoding to convert the string to before escaping
+///  - exclude       - list of characters to ignore (in addition to a-zA-Z0-9)
+///  - keep_escaped  - don't encode '%' in a correct escape sequence
+///
+/// Characters that can't be represented in the given encoding are replaced
+/// with percent-encoded HTML numeric character references (e.g. `☃` -> `%26%239731%3B`),
+/// same as browsers do when submitting forms. These are always escaped,
+/// regardless of `exclude`, so they can't introduce new url delimiters.
+///
+/// Requires `encoding` feature.
+///
+/// ```rust
+/// use mdurl::urlencode::{encode_with_encoding, ENCODE_COMPONENT_CHARS};
+///
+/// let encoded = encode_with_encoding("привет", encoding_rs::WINDOWS_1251, ENCODE_COMPONENT_CHARS, true);
+/// assert_eq!(encoded, "%EF%F0%E8%E2%E5%F2");
+///
+/// let encoded = encode_with_encoding("a☃", encoding_rs::WINDOWS_1251, ENCODE_COMPONENT_CHARS, true);
+/// assert_eq!(encoded, "a%26%239731%3B");
+/// ```
+pub fn encode_with_encoding<'a>(string: &'a str, encoding: &'static Encoding, exclude: AsciiSet, keep_escaped: bool) -> Cow<'a, str> {
+    match encoding.encode(string) {
+        (Cow::Borrowed(bytes), _, _) => encode_bytes(bytes, exclude, keep_escaped),
+        (Cow::Owned(bytes), _, false) => Cow::Owned(encode_bytes(&bytes, exclude, keep_escaped).into_owned()),
+        (Cow::Owned(_), _, true) => Cow::Owned(encode_with_references(string, encoding, exclude, keep_escaped)),
+    }
+}
+
+// Slow path of `encode_with_encoding` for strings with unmappable characters,
+// bytes between them are escaped as usual, and references are escaped separately.
+fn encode_with_references(string: &str, encoding: &'static Encoding, exclude: AsciiSet, keep_escaped: bool) -> String {
+    let mut encoder = encoding.new_encoder();
+    let mut result = String::with_capacity(string.len() * 3);
+    let mut bytes = Vec::new();
+    let mut buffer = [0u8; 1024];
+    let mut input = string;
+
+    loop {
+        let (status, read, written) = encoder.encode_from_utf8_without_replacement(input, &mut buffer, true);
+        bytes.extend_from_slice(&buffer[..written]);
+        input = &input[read..];
+
+        match status {
+            EncoderResult::InputEmpty => break,
+            EncoderResult::OutputFull => {}
+            EncoderResult::Unmappable(ch) => {
+                result.push_str(&encode_bytes(&bytes, exclude, keep_escaped));
+                bytes.clear();
+                let _ = write!(result, "%26%23{}%3B", ch as u32);
+            }
+        }
+    }
+
+    result.push_str(&encode_bytes(&bytes, exclude, keep_escaped));
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use super::{decode_with_encoding, encode_with_encoding};
+    use super::AsciiSet;
+    const SET : AsciiSet = AsciiSet::from(";/?:@&=+$,#");
+
+    #[test]
+    fn should_decode_windows_1251() {
+        let decoded = decode_with_encoding("/wiki/%CC%EE%F1%EA%E2%E0", encoding_rs::WINDOWS_1251, SET);
+        assert_eq!(decoded, "/wiki/Москва");
+    }
+
+    #[test]
+    fn should_decode_shift_jis_with_ascii_trail_bytes() {
+        // `ア` is 0x83 0x41 in Shift_JIS, second byte is `A`
+        let decoded = decode_with_encoding("%83A%83C", encoding_rs::SHIFT_JIS, SET);
+        assert_eq!(decoded, "アイ");
+    }
+
+    #[test]
+    fn should_keep_excluded_and_nonascii_chars() {
+        let decoded = decode_with_encoding("тест%2F%EF%F0%E8%2f", encoding_rs::WINDOWS_1251, SET);
+        assert_eq!(decoded, "тест%2Fпри%2f");
+    }
+
+    #[test]
+    fn should_replace_invalid_sequences() {
+        let decoded = decode_with_encoding("%82", encoding_rs::SHIFT_JIS, SET);
+        assert_eq!(decoded, "\u{fffd}");
+    }
+
+    #[test]
+    fn should_borrow_if_nothing_is_decoded() {
+        let decoded = decode_with_encoding("тест%2F%", encoding_rs::WINDOWS_1251, SET);
+        assert!(matches!(decoded, Cow::Borrowed("тест%2F%")));
+    }
+
+    #[test]
+    fn should_encode_windows_1251() {
+        let encoded = encode_with_encoding("/wiki/Москва", encoding_rs::WINDOWS_1251, SET, true);
+        assert_eq!(encoded, "/wiki/%CC%EE%F1%EA%E2%E0");
+    }
+
+    #[test]
+    fn should_encode_unmappable_chars_as_references() {
+        let encoded = encode_with_encoding("a☃", encoding_rs::WINDOWS_1251, SET, true);
+        assert_eq!(encoded, "a%26%239731%3B");
+
+        let encoded = encode_with_encoding("?q=☃&x=日本", encoding_rs::WINDOWS_1251, SET, true);
+        assert_eq!(encoded, "?q=%26%239731%3B&x=%26%2326085%3B%26%2326412%3B");
+
+        let encoded = encode_with_encoding("☃%41", encoding_rs::SHIFT_JIS, SET, true);
+        assert_eq!(encoded, "%26%239731%3B%41");
+    }
+
+    #[test]
+    fn encode_should_round_trip() {
+        let encoded = encode_with_encoding("日本語 テスト", encoding_rs::SHIFT_JIS, SET, true);
+        assert_eq!(decode_with_encoding(&encoded, encoding_rs::SHIFT_JIS, SET), "日本語 テスト");
+        assert!(matches!(encode_with_encoding("hello", encoding_rs::SHIFT_JIS, SET, true), Cow::Borrowed("hello")));
+    }
+}
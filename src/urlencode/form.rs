@@ -0,0 +1,150 @@
+use alloc::borrow::Cow;
+use alloc::string::String;
+use alloc::vec::Vec;
+use super::{decode, encode, encode_into, AsciiSet, DECODE_COMPONENT_CHARS};
+
+/// [application/x-www-form-urlencoded](https://url.spec.whatwg.org/#application/x-www-form-urlencoded)
+/// character set.
+///
+/// `encode(s, FORM_URLENCODED, false)` escapes all characters except `A-Za-z0-9*-._`,
+/// use [encode_form] to also replace spaces with `+`.
+pub const FORM_URLENCODED : AsciiSet = AsciiSet::from("*-._");
+
+/// Encode a name or a value using `application/x-www-form-urlencoded` rules,
+/// e.g. `hello world&` -> `hello+world%26`.
+///
+/// ```rust
+/// use mdurl::urlencode::encode_form;
+///
+/// assert_eq!(encode_form("1 + 1 = 2"), "1+%2B+1+%3D+2");
+/// ```
+pub fn encode_form(string: &str) -> Cow<'_, str> {
+    match encode(string, FORM_URLENCODED.add(b' '), false) {
+        Cow::Borrowed(s) if !s.contains(' ') => Cow::Borrowed(s),
+        s => Cow::Owned(s.replace(' ', "+")),
+    }
+}
+
+/// Decode a name or a value using `application/x-www-form-urlencoded` rules,
+/// e.g. `hello+world%26` -> `hello world&`.
+///
+/// All percent-encoded sequences are decoded, invalid UTF-8 sequences are replaced with `U+FFFD`.
+///
+/// ```rust
+/// use mdurl::urlencode::decode_form;
+///
+/// assert_eq!(decode_form("1+%2B+1+%3D+2"), "1 + 1 = 2");
+/// ```
+pub fn decode_form(string: &str) -> Cow<'_, str> {
+    if !string.contains('+') {
+        return decode(string, DECODE_COMPONENT_CHARS);
+    }
+
+    let string = string.replace('+', " ");
+    Cow::Owned(decode(&string, DECODE_COMPONENT_CHARS).into_owned())
+}
+
+/// Parse `application/x-www-form-urlencoded` string into a list of name-value pairs.
+///
+/// Leading `?` is not removed, so you should strip it yourself when parsing
+/// [Url::search](crate::Url::search).
+///
+/// ```rust
+/// use mdurl::urlencode::parse_form;
+///
+/// assert_eq!(parse_form("q=hello+world&lang=en&&x"), [
+///     ("q".into(), "hello world".into()),
+///     ("lang".into(), "en".into()),
+///     ("x".into(), "".into()),
+/// ]);
+/// ```
+pub fn parse_form(string: &str) -> Vec<(Cow<'_, str>, Cow<'_, str>)> {
+    string.split('&')
+        .filter(|s| !s.is_empty())
+        .map(|s| {
+            let (name, value) = s.split_once('=').unwrap_or((s, ""));
+            (decode_form(name), decode_form(value))
+        })
+        .collect()
+}
+
+/// Serialize a list of name-value pairs into `application/x-www-form-urlencoded` string.
+///
+/// ```rust
+/// use mdurl::urlencode::serialize_form;
+///
+/// assert_eq!(serialize_form([("q", "hello world"), ("lang", "en")]), "q=hello+world&lang=en");
+/// ```
+pub fn serialize_form<K: AsRef<str>, V: AsRef<str>>(pairs: impl IntoIterator<Item = (K, V)>) -> String {
+    let mut result = String::new();
+
+    for (name, value) in pairs {
+        if !result.is_empty() {
+            result.push('&');
+        }
+        encode_form_into(name.as_ref(), &mut result);
+        result.push('=');
+        encode_form_into(value.as_ref(), &mut result);
+    }
+
+    result
+}
+
+fn encode_form_into(string: &str, output: &mut String) {
+    for (i, part) in string.split(' ').enumerate() {
+        if i > 0 {
+            output.push('+');
+        }
+        encode_into(part, FORM_URLENCODED, false, output);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::borrow::Cow;
+    use alloc::vec::Vec;
+    use super::{decode_form, encode_form, parse_form, serialize_form};
+
+    #[test]
+    fn should_encode_spaces_as_plus() {
+        assert_eq!(encode_form("a b  c"), "a+b++c");
+    }
+
+    #[test]
+    fn should_encode_reserved_chars() {
+        assert_eq!(encode_form("+&=%20~!"), "%2B%26%3D%2520%7E%21");
+        assert_eq!(encode_form("*-._"), "*-._");
+        assert_eq!(encode_form("日本"), "%E6%97%A5%E6%9C%AC");
+    }
+
+    #[test]
+    fn should_borrow_if_nothing_is_changed() {
+        assert!(matches!(encode_form("hello"), Cow::Borrowed("hello")));
+        assert!(matches!(decode_form("hello"), Cow::Borrowed("hello")));
+    }
+
+    #[test]
+    fn should_decode_plus_as_space() {
+        assert_eq!(decode_form("a+b%2Bc%20d"), "a b+c d");
+        assert_eq!(decode_form("%2F%3F%ff"), "/?\u{fffd}");
+    }
+
+    #[test]
+    fn parse_should_split_pairs() {
+        let pairs = parse_form("a=1&b==2&&c&=d&a=%26");
+        let pairs = pairs.iter().map(|(k, v)| (k.as_ref(), v.as_ref())).collect::<Vec<_>>();
+        assert_eq!(pairs, [("a", "1"), ("b", "=2"), ("c", ""), ("", "d"), ("a", "&")]);
+    }
+
+    #[test]
+    fn serialize_should_round_trip() {
+        let pairs = [("q", "rust & c++"), ("empty", ""), ("π", "3.14")];
+        let serialized = serialize_form(pairs);
+        assert_eq!(serialized, "q=rust+%26+c%2B%2B&empty=&%CF%80=3.14");
+
+        let parsed = parse_form(&serialized);
+        let parsed = parsed.iter().map(|(k, v)| (k.as_ref(), v.as_ref())).collect::<Vec<_>>();
+        assert_eq!(parsed, pairs);
+    }
+}
@@ -1,22 +1,47 @@
 //! Percent-encoding and decoding that gracefully handles invalid input.
 //!
-//! **You should use [percent_encoding](https://crates.io/crates/percent-encoding) crate instead.**
-//!
 //! The purpose of this implementation is to keep user input intact in corner cases,
-//! for example if url is already encoded, we try to keep it as is.
+//! for example if url is already encoded, we try to keep it as is (see `keep_escaped`
+//! argument of [encode]), and invalid escape sequences are left untouched by [decode].
+//!
+//! ```rust
+//! use mdurl::urlencode::{decode, encode, DECODE_DEFAULT_CHARS, ENCODE_DEFAULT_CHARS};
 //!
-//! You probably don't need it, so use crates designed to work with urlencoding specifically,
-//! as they are better tested and maintained.
+//! assert_eq!(encode("/caf%C3%A9 100%", ENCODE_DEFAULT_CHARS, true), "/caf%C3%A9%20100%25");
+//! assert_eq!(decode("/caf%C3%A9%20100%", DECODE_DEFAULT_CHARS), "/café 100%");
+//! ```
 //!
-//! This is not a part of public api, but still accessible. Please tell me
-//! if someone finds it useful enough to make it public.
+//! Character sets are described with [AsciiSet], which lists characters that
+//! are *not* encoded (or decoded). Commonly used sets are provided as constants here
+//! and in [whatwg] module, and there are helpers for specific url components:
+//! [encode_path_segment], [encode_query_value], [encode_fragment], [encode_userinfo].
+//!
+//! This module is a part of public API and follows semver, same as the rest of the crate.
+//! If you need fully spec-compliant behavior instead, use
+//! [percent_encoding](https://crates.io/crates/percent-encoding) crate.
 //!
 
 mod asciiset;
 pub use asciiset::AsciiSet;
 
+mod component;
+pub use component::*;
+
 mod decode;
 pub use decode::*;
 
 mod encode;
 pub use encode::*;
+
+#[cfg(feature = "encoding")]
+mod encoding;
+#[cfg(feature = "encoding")]
+#[cfg_attr(docsrs, doc(cfg(feature = "encoding")))]
+pub use encoding::*;
+
+mod form;
+pub use form::*;
+
+pub mod whatwg;
+
+mod utf8;
@@ -0,0 +1,89 @@
+// Streaming UTF-8 validator used by percent-decoder.
+//
+// It is fed one byte at a time, and follows the same rules as
+// `String::from_utf8_lossy` (and WHATWG encoding standard): each maximal
+// prefix of a valid sequence is reported as a single invalid sequence.
+//
+use super::DecodeErrorKind;
+
+pub(super) struct Utf8Validator {
+    code_point: u32,
+    first: u8,
+    remaining: u8,
+    lower: u8,
+    upper: u8,
+    len: u8,
+}
+
+pub(super) enum Utf8Step {
+    // byte is consumed, more bytes are needed to complete a character
+    Incomplete,
+    // byte is consumed, and it completes this character
+    Char(char),
+    // bytes collected so far form an invalid sequence,
+    // if `consumed` is false, current byte is not a part of it and should be pushed again
+    Invalid { consumed: bool, kind: DecodeErrorKind },
+}
+
+impl Utf8Validator {
+    pub(super) const fn new() -> Self {
+        Self { code_point: 0, first: 0, remaining: 0, lower: 0x80, upper: 0xbf, len: 0 }
+    }
+
+    // number of bytes in an incomplete sequence collected so far
+    pub(super) fn pending(&self) -> usize {
+        self.len as usize
+    }
+
+    pub(super) fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    pub(super) fn push(&mut self, byte: u8) -> Utf8Step {
+        if self.remaining == 0 {
+            let (remaining, lower, upper, mask) = match byte {
+                0x00..=0x7f => return Utf8Step::Char(byte as char),
+                0xc2..=0xdf => (1, 0x80, 0xbf, 0x1f),
+                0xe0        => (2, 0xa0, 0xbf, 0x0f),
+                0xe1..=0xec => (2, 0x80, 0xbf, 0x0f),
+                0xed        => (2, 0x80, 0x9f, 0x0f),
+                0xee..=0xef => (2, 0x80, 0xbf, 0x0f),
+                0xf0        => (3, 0x90, 0xbf, 0x07),
+                0xf1..=0xf3 => (3, 0x80, 0xbf, 0x07),
+                0xf4        => (3, 0x80, 0x8f, 0x07),
+                0xc0..=0xc1 => return Utf8Step::Invalid { consumed: true, kind: DecodeErrorKind::Overlong },
+                _ => return Utf8Step::Invalid { consumed: true, kind: DecodeErrorKind::InvalidUtf8 },
+            };
+
+            *self = Self { code_point: (byte & mask) as u32, first: byte, remaining, lower, upper, len: 1 };
+            return Utf8Step::Incomplete;
+        }
+
+        if byte < self.lower || byte > self.upper {
+            // second byte is restricted to exclude overlong forms and surrogates,
+            // and we report those separately
+            let kind = match (self.len, self.first, byte) {
+                (1, 0xe0, 0x80..=0x9f) => DecodeErrorKind::Overlong,
+                (1, 0xf0, 0x80..=0x8f) => DecodeErrorKind::Overlong,
+                (1, 0xed, 0xa0..=0xbf) => DecodeErrorKind::Surrogate,
+                _ => DecodeErrorKind::InvalidUtf8,
+            };
+            self.reset();
+            return Utf8Step::Invalid { consumed: false, kind };
+        }
+
+        self.code_point = self.code_point << 6 | (byte & 0x3f) as u32;
+        self.remaining -= 1;
+        self.lower = 0x80;
+        self.upper = 0xbf;
+        self.len += 1;
+
+        if self.remaining > 0 {
+            return Utf8Step::Incomplete;
+        }
+
+        let ch = char::from_u32(self.code_point).unwrap_or(char::REPLACEMENT_CHARACTER);
+        self.reset();
+        Utf8Step::Char(ch)
+    }
+}
@@ -0,0 +1,108 @@
+//! Character sets defined in [WHATWG URL standard](https://url.spec.whatwg.org/#percent-encoded-bytes).
+//!
+//! The standard defines *percent-encode sets*, i.e. characters that must be encoded,
+//! while [encode](super::encode) accepts characters that must be left as is.
+//! So each constant here is a complement of the corresponding percent-encode set,
+//! and can be passed to [encode](super::encode) directly:
+//!
+//! ```rust
+//! use mdurl::urlencode::{encode, whatwg};
+//!
+//! assert_eq!(encode("/a b/{c}?", whatwg::PATH, false), "/a%20b/%7Bc%7D%3F");
+//! assert_eq!(encode("/a b/{c}?", whatwg::QUERY, false), "/a%20b/{c}?");
+//! ```
+//!
+//! Note that `%` is not a part of most percent-encode sets, so it's never encoded
+//! with those, regardless of `keep_escaped` argument.
+//!
+use super::AsciiSet;
+
+/// Complement of [C0 control percent-encode set](https://url.spec.whatwg.org/#c0-control-percent-encode-set),
+/// all printable ASCII characters (`0x20..=0x7e`).
+pub const C0_CONTROL : AsciiSet = AsciiSet::new().add_range(0x20, 0x7e);
+
+/// Complement of [fragment percent-encode set](https://url.spec.whatwg.org/#fragment-percent-encode-set),
+/// [C0_CONTROL] except `` "<>` `` and space.
+pub const FRAGMENT : AsciiSet = C0_CONTROL.intersection(AsciiSet::from(" \"<>`").complement());
+
+/// Complement of [query percent-encode set](https://url.spec.whatwg.org/#query-percent-encode-set),
+/// [C0_CONTROL] except `"#<>` and space.
+pub const QUERY : AsciiSet = C0_CONTROL.intersection(AsciiSet::from(" \"#<>").complement());
+
+/// Complement of [special-query percent-encode set](https://url.spec.whatwg.org/#special-query-percent-encode-set),
+/// [QUERY] except `'`.
+pub const SPECIAL_QUERY : AsciiSet = QUERY.remove(b'\'');
+
+/// Complement of [path percent-encode set](https://url.spec.whatwg.org/#path-percent-encode-set),
+/// [QUERY] except `` ?^`{} ``.
+pub const PATH : AsciiSet = QUERY.intersection(AsciiSet::from("?^`{}").complement());
+
+/// Complement of [userinfo percent-encode set](https://url.spec.whatwg.org/#userinfo-percent-encode-set),
+/// [PATH] except `/:;=@[\]|`.
+pub const USERINFO : AsciiSet = PATH.intersection(AsciiSet::from("/:;=@[\\]|").complement());
+
+/// Complement of [component percent-encode set](https://url.spec.whatwg.org/#component-percent-encode-set),
+/// [USERINFO] except `$%&+,`.
+///
+/// It gives identical results to JavaScript's `encodeURIComponent()`,
+/// same as [ENCODE_COMPONENT_CHARS](super::ENCODE_COMPONENT_CHARS).
+pub const COMPONENT : AsciiSet = USERINFO.intersection(AsciiSet::from("$%&+,").complement());
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::urlencode::{encode, ENCODE_COMPONENT_CHARS, FORM_URLENCODED};
+
+    const PRINTABLE : &str = " !\"#$%&'()*+,-./0123456789:;<=>?@ABCDEFGHIJKLMNOPQRSTUVWXYZ[\\]^_`abcdefghijklmnopqrstuvwxyz{|}~";
+
+    #[test]
+    fn c0_control() {
+        assert_eq!(encode("\x00\x1f\x7f", C0_CONTROL, false), "%00%1F%7F");
+        assert_eq!(encode(PRINTABLE, C0_CONTROL, false), PRINTABLE);
+    }
+
+    #[test]
+    fn fragment() {
+        assert_eq!(encode(" \"<>`#?{}%", FRAGMENT, false), "%20%22%3C%3E%60#?{}%");
+    }
+
+    #[test]
+    fn query() {
+        assert_eq!(encode(" \"#<>'?{}", QUERY, false), "%20%22%23%3C%3E'?{}");
+        assert_eq!(encode(" \"#<>'?{}", SPECIAL_QUERY, false), "%20%22%23%3C%3E%27?{}");
+    }
+
+    #[test]
+    fn path() {
+        assert_eq!(encode("?^`{}/:@|", PATH, false), "%3F%5E%60%7B%7D/:@|");
+    }
+
+    #[test]
+    fn userinfo() {
+        assert_eq!(encode("/:;=@[\\]|$&+,", USERINFO, false), "%2F%3A%3B%3D%40%5B%5C%5D%7C$&+,");
+    }
+
+    #[test]
+    fn component() {
+        assert_eq!(COMPONENT.add_alphanumeric(), ENCODE_COMPONENT_CHARS.add_alphanumeric());
+        assert_eq!(encode(PRINTABLE, COMPONENT, false), encode(PRINTABLE, ENCODE_COMPONENT_CHARS, false));
+    }
+
+    #[test]
+    fn sets_should_be_nested() {
+        // each set in the standard is a superset of the previous one,
+        // so complements are subsets
+        for (smaller, larger) in [
+            (FRAGMENT, C0_CONTROL),
+            (QUERY, C0_CONTROL),
+            (SPECIAL_QUERY, QUERY),
+            (PATH, QUERY),
+            (USERINFO, PATH),
+            (COMPONENT, USERINFO),
+            (FORM_URLENCODED.add_alphanumeric(), COMPONENT),
+        ] {
+            assert_eq!(smaller.intersection(larger), smaller);
+        }
+    }
+}
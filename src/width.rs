@@ -0,0 +1,246 @@
+use core::ops::{Add, Sub};
+
+/// How to measure text length when fitting url into [FormatOptions::max_length](crate::FormatOptions::max_length).
+///
+/// ```rust
+/// # #[cfg(feature = "width")] {
+/// use mdurl::{format_url_for_humans_with, FormatOptions, WidthMode};
+///
+/// let url = "https://ja.wikipedia.org/wiki/日本語の表記体系";
+/// let options = FormatOptions { max_length: 28, width_mode: WidthMode::Columns, ..Default::default() };
+///
+/// // each CJK character takes two columns in a terminal
+/// assert_eq!(format_url_for_humans_with(url, &options), "ja.wikipedia.org/…/日本語の…");
+/// # }
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum WidthMode {
+    /// Count unicode code points, same as `str::chars().count()`.
+    #[default]
+    Chars,
+
+    /// Count columns that text takes in a monospace font (e.g. in terminal),
+    /// according to [UAX #11](https://www.unicode.org/reports/tr11/):
+    ///
+    ///  - East Asian wide and fullwidth characters (including most emoji) take 2 columns
+    ///  - combining marks and other zero-width characters take 0 columns
+    ///  - characters joined with zero-width joiner (U+200D) are counted as the first one
+    ///
+    /// Text is never truncated between a character and its combining marks,
+    /// or inside a zero-width joiner sequence.
+    ///
+    /// Requires `width` feature.
+    #[cfg(feature = "width")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "width")))]
+    Columns,
+}
+
+/// Text measurer for [format_url_for_humans_measured](crate::format_url_for_humans_measured),
+/// e.g. font metrics of a GUI toolkit.
+///
+/// It is implemented for closures, so you can pass `|s: &str| font.width(s)` directly.
+///
+/// ```rust
+/// use mdurl::format_url_for_humans_measured;
+///
+/// // pretend that narrow letters take 4 pixels and everything else takes 8
+/// let measure = |s: &str| s.chars().map(|ch| if "ilj./".contains(ch) { 4. } else { 8. }).sum::<f32>();
+/// let url = "https://www.example.org/wiki/lorem/ipsum/dolor.html";
+///
+/// assert_eq!(format_url_for_humans_measured(url, 200., &measure), "example.org/…/dolor.html");
+/// ```
+pub trait TextMeasure {
+    /// Width of the text when rendered, in arbitrary units (e.g. pixels).
+    fn width(&self, text: &str) -> f32;
+}
+
+impl<F: Fn(&str) -> f32> TextMeasure for F {
+    fn width(&self, text: &str) -> f32 {
+        self(text)
+    }
+}
+
+// Length of text used in url elision, integer for characters and columns,
+// and floating point for widths returned by [TextMeasure].
+pub(crate) trait Length: Copy + Default + PartialOrd + Add<Output = Self> + Sub<Output = Self> {
+    fn saturating_add(self, other: Self) -> Self;
+    fn saturating_sub(self, other: Self) -> Self;
+}
+
+impl Length for usize {
+    fn saturating_add(self, other: Self) -> Self {
+        usize::saturating_add(self, other)
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        usize::saturating_sub(self, other)
+    }
+}
+
+impl Length for f32 {
+    fn saturating_add(self, other: Self) -> Self {
+        self + other
+    }
+
+    fn saturating_sub(self, other: Self) -> Self {
+        (self - other).max(0.)
+    }
+}
+
+// Measurement used in url elision.
+pub(crate) trait Measure {
+    type Length: Length;
+
+    // Total length of the text.
+    fn measure(&self, text: &str) -> Self::Length;
+
+    // Length of query and fragment, which is allowed to overflow the limit before
+    // they get truncated (historically it's byte length when counting characters).
+    fn measure_query(&self, text: &str) -> Self::Length;
+
+    // Split text into parts that must not be separated when truncating,
+    // and call `f` with byte offset and length of each part.
+    fn for_each_unit(&self, text: &str, graphemes: bool, f: impl FnMut(usize, Self::Length));
+}
+
+impl Measure for WidthMode {
+    type Length = usize;
+
+    fn measure(&self, text: &str) -> usize {
+        WidthMode::measure(*self, text)
+    }
+
+    fn measure_query(&self, text: &str) -> usize {
+        match self {
+            // same as in older versions of this crate, so default output doesn't change
+            WidthMode::Chars => text.len(),
+
+            #[cfg(feature = "width")]
+            WidthMode::Columns => WidthMode::measure(*self, text),
+        }
+    }
+
+    fn for_each_unit(&self, text: &str, graphemes: bool, mut f: impl FnMut(usize, usize)) {
+        self.units(text, graphemes).for_each(|(offset, length)| f(offset, length));
+    }
+}
+
+// [TextMeasure] adapter, it's a separate type to avoid conflicting implementations.
+pub(crate) struct Measured<'a, M: TextMeasure>(pub(crate) &'a M);
+
+impl<M: TextMeasure> Measure for Measured<'_, M> {
+    type Length = f32;
+
+    fn measure(&self, text: &str) -> f32 {
+        self.0.width(text)
+    }
+
+    fn measure_query(&self, text: &str) -> f32 {
+        self.0.width(text)
+    }
+
+    fn for_each_unit(&self, text: &str, graphemes: bool, mut f: impl FnMut(usize, f32)) {
+        let mut units = WidthMode::Chars.units(text, graphemes).map(|(offset, _)| offset).peekable();
+        while let Some(offset) = units.next() {
+            let end = units.peek().copied().unwrap_or(text.len());
+            f(offset, self.0.width(&text[offset..end]));
+        }
+    }
+}
+
+impl WidthMode {
+    // Total length of the text.
+    pub(crate) fn measure(self, text: &str) -> usize {
+        self.units(text, false).map(|(_, length)| length).sum()
+    }
+
+    // Split text into parts that must not be separated when truncating,
+    // returning byte offset and length of each part.
+    //
+    // If `graphemes` is true, each part is an extended grapheme cluster
+    // (requires `grapheme` feature, ignored otherwise).
+    pub(crate) fn units(self, text: &str, graphemes: bool) -> impl Iterator<Item = (usize, usize)> + '_ {
+        #[cfg(feature = "grapheme")]
+        let mut clusters = graphemes.then(|| unicode_segmentation::UnicodeSegmentation::grapheme_indices(text, true));
+        #[cfg(not(feature = "grapheme"))]
+        let _ = graphemes;
+
+        let mut chars = text.char_indices().peekable();
+
+        core::iter::from_fn(move || {
+            #[cfg(feature = "grapheme")]
+            if let Some(clusters) = &mut clusters {
+                return clusters.next().map(|(offset, cluster)| (offset, self.measure(cluster)));
+            }
+
+            match self {
+                WidthMode::Chars => chars.next().map(|(offset, _)| (offset, 1)),
+
+                #[cfg(feature = "width")]
+                WidthMode::Columns => {
+                    use unicode_width::UnicodeWidthChar;
+
+                    let (offset, ch) = chars.next()?;
+                    let mut last = ch;
+                    while let Some(&(_, next)) = chars.peek() {
+                        if last != '\u{200d}' && next.width().unwrap_or(0) != 0 {
+                            break;
+                        }
+                        last = next;
+                        chars.next();
+                    }
+
+                    Some((offset, ch.width().unwrap_or(0)))
+                }
+            }
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use super::WidthMode;
+
+    #[test]
+    fn chars() {
+        assert_eq!(WidthMode::Chars.measure("日本e\u{301}"), 4);
+        assert_eq!(WidthMode::Chars.units("aé", false).collect::<Vec<_>>(), [(0, 1), (1, 1)]);
+    }
+
+    #[test]
+    #[cfg(feature = "width")]
+    fn columns() {
+        assert_eq!(WidthMode::Columns.measure("abc"), 3);
+        assert_eq!(WidthMode::Columns.measure("日本語"), 6);
+        assert_eq!(WidthMode::Columns.measure("ｆｕｌｌ"), 8);
+        assert_eq!(WidthMode::Columns.measure("e\u{301}"), 1);
+        assert_eq!(WidthMode::Columns.measure("🦀"), 2);
+        // family: man, zwj, woman, zwj, girl
+        assert_eq!(WidthMode::Columns.measure("👨\u{200d}👩\u{200d}👧"), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "width")]
+    fn columns_should_keep_sequences_together() {
+        let units = WidthMode::Columns.units("ae\u{301}日👨\u{200d}👩x", false).collect::<Vec<_>>();
+        assert_eq!(units, [(0, 1), (1, 1), (4, 2), (7, 2), (18, 1)]);
+    }
+
+    #[test]
+    #[cfg(feature = "grapheme")]
+    fn graphemes() {
+        // flag of Japan (two regional indicators) and Devanagari "ki" (consonant + vowel sign)
+        let units = WidthMode::Chars.units("🇯🇵कि", true).collect::<Vec<_>>();
+        assert_eq!(units, [(0, 2), (8, 2)]);
+    }
+
+    #[test]
+    #[cfg(all(feature = "grapheme", feature = "width"))]
+    fn graphemes_columns() {
+        let units = WidthMode::Columns.units("🇯🇵a\u{301}", true).collect::<Vec<_>>();
+        assert_eq!(units, [(0, 2), (8, 1)]);
+    }
+}
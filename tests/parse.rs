@@ -1304,3 +1304,70 @@ fn delims2() {
         }
     );
 }
+
+#[test]
+fn long_hostname_part() {
+    let part = "a".repeat(64);
+    assert_eq!(
+        parse_url(&format!("http://{part}.com/x")),
+        Url {
+            protocol: Some("http:".into()),
+            slashes: true,
+            hostname: Some("a".repeat(63)),
+            pathname: Some("a.com/x".into()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn long_hostname_part_nonascii() {
+    // non-ASCII characters are counted once per char, not per byte
+    let part = "ä".repeat(63);
+    assert_eq!(
+        parse_url(&format!("http://{part}.com/x")),
+        Url {
+            protocol: Some("http:".into()),
+            slashes: true,
+            hostname: Some(format!("{part}.com")),
+            pathname: Some("/x".into()),
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+fn invalid_hostname_with_port() {
+    assert_eq!(
+        parse_url("http://日本::1/"),
+        Url {
+            protocol: Some("http:".into()),
+            slashes: true,
+            hostname: Some("".into()),
+            pathname: Some("日本::1/".into()),
+            ..Default::default()
+        }
+    );
+
+    assert_eq!(
+        parse_url("//日本::1"),
+        Url {
+            slashes: true,
+            hostname: Some("".into()),
+            pathname: Some("日本::1".into()),
+            ..Default::default()
+        }
+    );
+
+    assert_eq!(
+        parse_url("http://a^b:80/x"),
+        Url {
+            protocol: Some("http:".into()),
+            slashes: true,
+            hostname: Some("a".into()),
+            pathname: Some("^b:80/x".into()),
+            ..Default::default()
+        }
+    );
+    assert_eq!(parse_url("http://日本::1/").to_string(), "http://日本::1/");
+}